@@ -1,53 +1,436 @@
-use std::fs;
-use std::io::{self, Write};
+// Removes SPAdes intermediate files from finished assemblies.
+// The final assemblies, logs, and any files matching the keep patterns stay.
+// The removed files can be packed into a compressed tar first.
+use std::fs::{self, File};
+use std::io::{self, Result, Write};
 use std::path::{Path, PathBuf};
 
-use glob::glob;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use glob::Pattern;
 use walkdir::WalkDir;
 
-pub fn clean_spades_files(path: &Path) {
+use crate::error::Error;
+use crate::logger;
+use crate::utils;
+
+// SPAdes needs dataset.yaml to continue or reproduce a multi-library run.
+const KEPT_FILES: [&str; 6] = [
+    "contigs.fasta",
+    "scaffolds.fasta",
+    "spades.log",
+    "warnings.log",
+    "dataset.yaml",
+    logger::LOG_FILE,
+];
+
+const ARCHIVE_SUFFIX: &str = "_intermediates.tar.gz";
+
+#[derive(Default)]
+pub struct CleanOpts {
+    pub dry: bool,
+    pub keep: Vec<Pattern>,
+    pub archive: bool,
+    // Cleans unfinished runs too.
+    pub force: bool,
+}
+
+impl CleanOpts {
+    pub fn new(dry: bool, keep: &[&str]) -> std::result::Result<Self, Error> {
+        let keep = keep.iter()
+            .map(|p| Pattern::new(p)
+                .map_err(|e| Error::InvalidInput(format!("INVALID KEEP PATTERN {}: {}", p, e))))
+            .collect::<std::result::Result<Vec<Pattern>, Error>>()?;
+
+        Ok(Self { dry, keep, archive: false, force: false })
+    }
+
+    pub fn is_kept(&self, path: &Path) -> bool {
+        let name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => return true,
+        };
+
+        KEPT_FILES.contains(&name.as_ref())
+            || name.ends_with(ARCHIVE_SUFFIX)
+            || self.keep.iter().any(|p| p.matches(&name))
+    }
+}
+
+#[derive(Default)]
+struct CleanSize {
+    removed: u64,
+    archive: u64,
+    // The reason the sample was skipped.
+    skipped: Option<String>,
+}
+
+pub fn clean_spades_files(path: &Path, opts: &CleanOpts) -> std::result::Result<(), Error> {
     let io = io::stdout();
     let mut handle = io::BufWriter::new(io);
-    writeln!(handle, "\x1b[0;33mRemoved files and directories:\x1b[0m").unwrap();
-    WalkDir::new(path).into_iter()
-        .filter_map(|ok| ok.ok())
-        .filter(|e| e.path().ends_with("spades.log"))
-        .for_each(|e| {
-            let path = e.path().parent().unwrap().to_string_lossy();
-            let contents = find_files(&path);
-            remove_contents(&contents, &mut handle);
-        });
-    writeln!(handle).unwrap();
+    if opts.dry {
+        writeln!(handle, "\x1b[0;33mFiles and directories to remove:\x1b[0m")?;
+    } else {
+        writeln!(handle, "\x1b[0;33mRemoved files and directories:\x1b[0m")?;
+    }
+
+    let mut total = CleanSize::default();
+    let mut skipped = 0;
+    for dir in find_spades_dirs(path) {
+        let size = clean_dir(&dir, opts, &mut handle)?;
+        total.removed += size.removed;
+        total.archive += size.archive;
+        if size.skipped.is_some() {
+            skipped += 1;
+        }
+    }
+
+    writeln!(handle)?;
+    if skipped > 0 {
+        writeln!(handle, "Skipped\t\t: {} runs", skipped)?;
+    }
+    if opts.dry {
+        writeln!(handle, "Reclaimable\t: {}", utils::format_size(total.removed))?;
+    } else if opts.archive {
+        writeln!(handle, "Removed\t\t: {}", utils::format_size(total.removed))?;
+        writeln!(handle, "Archives\t: {}", utils::format_size(total.archive))?;
+        writeln!(handle, "Freed space\t: {}", 
+            utils::format_size(total.removed.saturating_sub(total.archive)))?;
+    } else {
+        writeln!(handle, "Freed space\t: {}", utils::format_size(total.removed))?;
+    }
+    writeln!(handle)?;
+    handle.flush()?;
+
+    Ok(())
+}
+
+// Cleans a sample right after the assembly.
+// Prints the freed space only, to keep the batch output short.
+pub fn clean_sample<W: Write>(
+    dir: &Path, 
+    opts: &CleanOpts, 
+    handle: &mut W
+) -> std::result::Result<(), Error> {
+    let size = clean_dir(dir, opts, &mut io::sink())?;
+    if let Some(reason) = size.skipped {
+        writeln!(handle, "\x1b[0;33mWarning\t\t: SKIPPED CLEANING. {}\x1b[0m", reason)?;
+    } else {
+        writeln!(handle, "Cleaned\t\t: {} freed", 
+            utils::format_size(size.removed.saturating_sub(size.archive)))?;
+    }
+    writeln!(handle)?;
+
+    Ok(())
+}
+
+fn clean_dir<W: Write>(
+    dir: &Path, 
+    opts: &CleanOpts, 
+    handle: &mut W
+) -> std::result::Result<CleanSize, Error> {
+    let mut size = CleanSize::default();
+    if let Some(reason) = get_skip_reason(dir, opts)? {
+        writeln!(handle, "\x1b[0;33mWarning\t\t: SKIPPED {}. {}\x1b[0m", 
+            dir.to_string_lossy(), reason)?;
+        size.skipped = Some(reason);
+        return Ok(size);
+    }
+
+    let contents = find_contents(dir, opts)?;
+    if contents.is_empty() {
+        return Ok(size);
+    }
+
+    if opts.archive && !opts.dry {
+        let archive = archive_contents(dir, &contents)?;
+        size.archive = get_size(&archive);
+        writeln!(handle, "{}\t{} (archive)", 
+            archive.to_string_lossy(), utils::format_size(size.archive))?;
+    }
+    size.removed = remove_contents(&contents, opts.dry, handle)?;
+
+    Ok(size)
+}
+
+// Skips unfinished runs and samples with an archive from an earlier cleanup.
+// The other samples are still cleaned.
+fn get_skip_reason(dir: &Path, opts: &CleanOpts) -> Result<Option<String>> {
+    if opts.force {
+        return Ok(None);
+    }
+
+    if let Err(msg) = check_finished(dir) {
+        return Ok(Some(format!("{}. USE --force TO CLEAN IT", msg)));
+    }
+
+    if opts.archive && !opts.dry {
+        let archive = get_archive_path(dir)?;
+        if archive.exists() {
+            return Ok(Some(format!("{} EXISTS. USE --force TO OVERWRITE IT", 
+                archive.to_string_lossy())));
+        }
+    }
+
+    Ok(None)
+}
+
+// SPAdes --continue needs the intermediate files of a crashed run.
+pub fn check_finished(dir: &Path) -> std::result::Result<(), &'static str> {
+    if !utils::is_spades_finished(&dir.join("spades.log")) {
+        return Err("SPAdes HAS NOT FINISHED");
+    }
+    if !dir.join("contigs.fasta").is_file() {
+        return Err("contigs.fasta IS MISSING");
+    }
+
+    Ok(())
+}
+
+// The sample ID is the SPAdes output directory name.
+pub fn get_sample_id(dir: &Path) -> Result<String> {
+    Ok(dir.canonicalize()?
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default())
+}
+
+fn get_archive_path(dir: &Path) -> Result<PathBuf> {
+    Ok(dir.join(format!("{}{}", get_sample_id(dir)?, ARCHIVE_SUFFIX)))
+}
+
+// Packs the contents into <id>_intermediates.tar.gz in the same directory.
+// An existing archive is overwritten. Only --force gets here with one.
+fn archive_contents(dir: &Path, contents: &[PathBuf]) -> std::result::Result<PathBuf, Error> {
+    let archive = get_archive_path(dir)?;
+
+    // Don't leave a partial archive that blocks the next run.
+    if let Err(e) = write_archive(&archive, dir, contents) {
+        fs::remove_file(&archive).ok();
+        return Err(e.into());
+    }
+
+    Ok(archive)
 }
 
-fn find_files(path: &str) -> Vec<PathBuf> {
-    let patterns = format!("{}/*", path);
+fn write_archive(archive: &Path, dir: &Path, contents: &[PathBuf]) -> Result<()> {
+    let file = File::create(archive)?;
+    let encoder = GzEncoder::new(file, Compression::default());
+    let mut tar = tar::Builder::new(encoder);
+    tar.follow_symlinks(false);
+    for path in contents {
+        let name = path.strip_prefix(dir).unwrap_or(path);
+        if path.is_dir() && !path.symlink_metadata()?.file_type().is_symlink() {
+            tar.append_dir_all(name, path)?;
+        } else {
+            tar.append_path_with_name(path, name)?;
+        }
+    }
+    tar.into_inner()?.finish()?;
+
+    Ok(())
+}
 
-    glob(&patterns)
-        .unwrap()
+// Every directory with a spades.log is a SPAdes output directory.
+// We collect them first to avoid walking through removed directories.
+// Nested ones are removed with their parents.
+pub fn find_spades_dirs(path: &Path) -> Vec<PathBuf> {
+    let dirs: Vec<PathBuf> = WalkDir::new(path).sort_by_file_name().into_iter()
         .filter_map(|ok| ok.ok())
+        .filter(|e| e.file_type().is_file() && e.file_name() == "spades.log")
+        .filter_map(|e| e.path().parent().map(Path::to_path_buf))
+        .collect();
+
+    dirs.iter()
+        .filter(|d| !dirs.iter().any(|p| p != *d && d.starts_with(p)))
+        .cloned()
         .collect()
 }
 
-fn remove_contents<W: Write>(contents: &[PathBuf], handle: &mut W) {
-    contents.iter()
-        .for_each(|e| {
-            if e.is_file() {
-                match e.to_string_lossy() {
-                    p if p.ends_with("/contigs.fasta") => (),
-                    p if p.ends_with("/scaffolds.fasta") => (),
-                    p if p.ends_with("/spades.log") => (),
-                    p if p.ends_with("/warnings.log") => (),
-                    _ => {
-                        fs::remove_file(e).unwrap();
-                        writeln!(handle, "{}", e.to_string_lossy()).unwrap();
-                    },
-                }
-            } 
-            if e.is_dir() {
-                fs::remove_dir_all(e).unwrap();
-                writeln!(handle, "{}", e.to_string_lossy()).unwrap();
-            }     
-        });
-
-}
\ No newline at end of file
+pub fn find_contents(dir: &Path, opts: &CleanOpts) -> Result<Vec<PathBuf>> {
+    let mut contents = fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>>>()?;
+    contents.retain(|p| !opts.is_kept(p));
+    contents.sort();
+
+    Ok(contents)
+}
+
+fn remove_contents<W: Write>(contents: &[PathBuf], dry: bool, handle: &mut W) -> Result<u64> {
+    let mut total = 0;
+    for path in contents {
+        let size = get_size(path);
+        if !dry {
+            remove_path(path)?;
+        }
+        writeln!(handle, "{}\t{}", path.to_string_lossy(), utils::format_size(size))?;
+        total += size;
+    }
+
+    Ok(total)
+}
+
+fn remove_path(path: &Path) -> Result<()> {
+    if path.is_dir() && !path.symlink_metadata()?.file_type().is_symlink() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+// Symlinks are counted by their own size, not their targets.
+pub fn get_size(path: &Path) -> u64 {
+    WalkDir::new(path).into_iter()
+        .filter_map(|ok| ok.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| !m.is_dir())
+        .map(|m| m.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_spades_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(name);
+        if dir.exists() {
+            fs::remove_dir_all(&dir).unwrap();
+        }
+        let sample = dir.join("sample");
+        fs::create_dir_all(sample.join("K21")).unwrap();
+        fs::create_dir_all(sample.join("corrected")).unwrap();
+        ["contigs.fasta", "scaffolds.fasta", "spades.log", "params.txt", "dataset.yaml",
+            "assembly_graph.fastg", "K21/final_contigs.fasta", "corrected/reads.fq.gz"]
+            .iter()
+            .for_each(|f| fs::write(sample.join(f), "ACGT").unwrap());
+        fs::write(sample.join("spades.log"), "======= SPAdes pipeline finished.").unwrap();
+
+        dir
+    }
+
+    #[test]
+    fn dry_run_test() {
+        let dir = create_spades_dir("spr_cleaner_dry_test");
+        let opts = CleanOpts::new(true, &["params.txt"]).unwrap();
+        let sample = dir.join("sample");
+        let contents = find_contents(&sample, &opts).unwrap();
+        let mut buff = Vec::new();
+        let total = remove_contents(&contents, opts.dry, &mut buff).unwrap();
+
+        assert_eq!(vec![sample.join("K21"), sample.join("assembly_graph.fastg"),
+            sample.join("corrected")], contents);
+        assert_eq!(12, total);
+        assert!(sample.join("K21").is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn clean_spades_files_test() {
+        let dir = create_spades_dir("spr_cleaner_test");
+        let opts = CleanOpts::new(false, &["*.fastg"]).unwrap();
+        clean_spades_files(&dir, &opts).unwrap();
+        let sample = dir.join("sample");
+
+        assert!(sample.join("contigs.fasta").is_file());
+        assert!(sample.join("assembly_graph.fastg").is_file());
+        assert!(sample.join("dataset.yaml").is_file());
+        assert!(!sample.join("params.txt").exists());
+        assert!(!sample.join("K21").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn archive_test() {
+        let dir = create_spades_dir("spr_cleaner_archive_test");
+        let mut opts = CleanOpts::new(false, &[]).unwrap();
+        opts.archive = true;
+        let sample = dir.join("sample");
+        let mut buff = Vec::new();
+        let size = clean_dir(&sample, &opts, &mut buff).unwrap();
+        let archive = sample.join("sample_intermediates.tar.gz");
+
+        assert!(archive.is_file());
+        assert!(!sample.join("corrected").exists());
+        assert_eq!(16, size.removed);
+        assert!(size.skipped.is_none());
+        assert!(size.archive > 0);
+
+        let file = File::open(&archive).unwrap();
+        let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+        let names: Vec<PathBuf> = tar.entries().unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_path_buf())
+            .collect();
+        assert!(names.contains(&PathBuf::from("K21/final_contigs.fasta")));
+        assert!(names.contains(&PathBuf::from("params.txt")));
+
+        let size = clean_dir(&sample, &opts, &mut buff).unwrap();
+        assert_eq!(0, size.removed);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn existing_archive_test() {
+        let dir = create_spades_dir("spr_cleaner_existing_archive_test");
+        let sample = dir.join("sample");
+        let other = dir.join("other");
+        fs::create_dir_all(other.join("K21")).unwrap();
+        ["contigs.fasta", "spades.log", "K21/final_contigs.fasta"].iter()
+            .for_each(|f| {
+                fs::copy(sample.join(f), other.join(f)).unwrap();
+            });
+        let archive = sample.join("sample_intermediates.tar.gz");
+        fs::write(&archive, "OLD").unwrap();
+        let mut opts = CleanOpts::new(false, &[]).unwrap();
+        opts.archive = true;
+        clean_spades_files(&dir, &opts).unwrap();
+
+        assert!(sample.join("K21").is_dir());
+        assert_eq!("OLD", fs::read_to_string(&archive).unwrap());
+        assert!(!other.join("K21").exists());
+        assert!(other.join("other_intermediates.tar.gz").is_file());
+
+        opts.force = true;
+        clean_spades_files(&dir, &opts).unwrap();
+        assert!(!sample.join("K21").exists());
+        assert_ne!(3, get_size(&archive));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unfinished_run_test() {
+        let dir = create_spades_dir("spr_cleaner_unfinished_test");
+        let sample = dir.join("sample");
+        fs::write(sample.join("spades.log"), "===== K21 started.").unwrap();
+        let mut opts = CleanOpts::default();
+        let mut buff = Vec::new();
+        let size = clean_dir(&sample, &opts, &mut buff).unwrap();
+
+        assert!(size.skipped.is_some());
+        assert!(sample.join("K21").is_dir());
+        assert!(String::from_utf8(buff).unwrap().contains("SPAdes HAS NOT FINISHED"));
+
+        fs::write(sample.join("spades.log"), "======= SPAdes pipeline finished.").unwrap();
+        fs::remove_file(sample.join("contigs.fasta")).unwrap();
+        assert!(check_finished(&sample).is_err());
+
+        opts.force = true;
+        let size = clean_dir(&sample, &opts, &mut Vec::new()).unwrap();
+        assert!(size.skipped.is_none());
+        assert!(!sample.join("K21").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn clean_sample_test() {
+        let dir = create_spades_dir("spr_cleaner_sample_test");
+        let sample = dir.join("sample");
+        let mut buff = Vec::new();
+        clean_sample(&sample, &CleanOpts::default(), &mut buff).unwrap();
+
+        assert_eq!("Cleaned\t\t: 16 B freed\n\n", String::from_utf8(buff).unwrap());
+        assert!(!sample.join("corrected").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,11 +2,19 @@ use std::path::PathBuf;
 
 use clap::{App, AppSettings, Arg, ArgMatches};
 
-use crate::cleaner;
+use crate::cleaner::{self, CleanOpts};
+use crate::error::Error;
+use crate::finder::{self, AutoSearch, DuplicateIds, IdSource, ReadPatterns};
 use crate::io;
+use crate::params::{self, Mode};
+use crate::parser::ConfigFormat;
+use crate::report::OutputFormat;
+use crate::runner::RunnerOpts;
+use crate::usage;
+use crate::validator;
 
-pub fn get_cli(version: &str) {
-    let args = App::new("SPAdes-runner")
+pub fn get_cli(version: &str) -> ArgMatches<'_> {
+    App::new("SPAdes-runner")
         .version(version)
         .about("Batch sequence assembly using SPAdes")
         .author("Heru Handika <hhandi1@lsu.edu>")
@@ -33,12 +41,62 @@ pub fn get_cli(version: &str) {
                     Arg::with_name("specify")
                         .short("s")
                         .long("specify")
-                        .help("Specifies clean read directory names")
+                        .help("Specifies clean read directory names. Accepts glob patterns")
                         .takes_value(true)
-                        .default_value("trimmed")
+                        .default_value("trimmed*")
                         .value_name("DIR NAME")
                 )
 
+                .arg(
+                    Arg::with_name("max-depth")
+                        .long("max-depth")
+                        .help("Limits how deep the auto search goes below the search dir")
+                        .takes_value(true)
+                        .value_name("N")
+                )
+
+                .arg(
+                    Arg::with_name("exclude")
+                        .long("exclude")
+                        .help("Skips directories matching the glob patterns")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("PATTERN")
+                )
+
+                .arg(
+                    Arg::with_name("follow-links")
+                        .long("follow-links")
+                        .help("Follows symbolic links to directories during auto search")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("id-component")
+                        .long("id-component")
+                        .help("Takes sample IDs from the N-th directory below the search dir")
+                        .takes_value(true)
+                        .conflicts_with_all(&["id-parent", "id-regex"])
+                        .value_name("N")
+                )
+
+                .arg(
+                    Arg::with_name("id-parent")
+                        .long("id-parent")
+                        .help("Takes sample IDs from the directory containing the clean reads")
+                        .takes_value(false)
+                        .conflicts_with("id-regex")
+                )
+
+                .arg(
+                    Arg::with_name("id-regex")
+                        .long("id-regex")
+                        .help("Takes sample IDs from the first capture group of a regex on read 1 file names")
+                        .takes_value(true)
+                        .value_name("REGEX")
+                )
+
                 .arg(
                     Arg::with_name("output")
                         .short("o")
@@ -55,6 +113,16 @@ pub fn get_cli(version: &str) {
                         .takes_value(false)
                 )
 
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .help("Sets the dry run output format")
+                        .takes_value(true)
+                        .possible_values(&["text", "json", "tsv"])
+                        .default_value("text")
+                        .value_name("FORMAT")
+                )
+
                 .arg(
                     Arg::with_name("threads")
                         .short("t")
@@ -69,8 +137,107 @@ pub fn get_cli(version: &str) {
                         .long("opts")
                         .help("Sets optional SPAdes params")
                         .takes_value(true)
+                        .allow_hyphen_values(true)
                         .value_name("OPTIONAL PARAMS")
                 )
+
+                .arg(
+                    Arg::with_name("mode")
+                        .long("mode")
+                        .help("Sets SPAdes pipeline mode")
+                        .takes_value(true)
+                        .default_value("default")
+                        .possible_values(&[
+                            "default", 
+                            "isolate", 
+                            "sc", 
+                            "meta", 
+                            "rna", 
+                            "plasmid", 
+                            "bio", 
+                            "corona"
+                        ])
+                        .value_name("MODE")
+                )
+
+                .arg(
+                    Arg::with_name("r1-pattern")
+                        .long("r1-pattern")
+                        .help("Sets a regex to match read 1 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_1_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("r2-pattern")
+                        .long("r2-pattern")
+                        .help("Sets a regex to match read 2 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_2_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("singleton-pattern")
+                        .long("singleton-pattern")
+                        .help("Sets a regex to match singleton file names")
+                        .takes_value(true)
+                        .default_value(finder::SINGLETON_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("duplicate-ids")
+                        .long("duplicate-ids")
+                        .help("Sets how to handle duplicate sample IDs")
+                        .takes_value(true)
+                        .possible_values(&["error", "suffix", "parent"])
+                        .default_value("error")
+                        .value_name("ACTION")
+                )
+
+                .arg(
+                    Arg::with_name("jobs")
+                        .short("j")
+                        .long("jobs")
+                        .help("Sets number of samples to assemble concurrently. Disables the stage progress if more than 1")
+                        .takes_value(true)
+                        .default_value("1")
+                        .value_name("JOB-NUM")
+                )
+
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .help("Skips finished samples and continues unfinished ones")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("auto-clean")
+                        .long("auto-clean")
+                        .help("Cleans unused SPAdes files of each sample right after the assembly")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("keep")
+                        .long("keep")
+                        .help("Keeps files and directories matching the glob patterns when cleaning")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .requires("auto-clean")
+                        .value_name("PATTERN")
+                )
+
+                .arg(
+                    Arg::with_name("decompress")
+                        .long("decompress")
+                        .help("Decompresses bzip2 and zstd reads before running SPAdes")
+                        .takes_value(false)
+                )
             )
 
         .subcommand(
@@ -83,6 +250,7 @@ pub fn get_cli(version: &str) {
                         .help("Inputs a config file")
                         .takes_value(true)
                         .value_name("INPUT")
+                        .required(true)
                 )
                 
                 .arg(
@@ -92,6 +260,16 @@ pub fn get_cli(version: &str) {
                         .takes_value(false)
                 )
 
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .help("Sets the dry run output format")
+                        .takes_value(true)
+                        .possible_values(&["text", "json", "tsv"])
+                        .default_value("text")
+                        .value_name("FORMAT")
+                )
+
                 .arg(
                     Arg::with_name("threads")
                         .short("t")
@@ -115,11 +293,282 @@ pub fn get_cli(version: &str) {
                         .long("opts")
                         .help("Sets optional SPAdes params")
                         .takes_value(true)
+                        .allow_hyphen_values(true)
                         .value_name("OPTIONAL PARAMS")
                 )
 
+                .arg(
+                    Arg::with_name("mode")
+                        .long("mode")
+                        .help("Sets SPAdes pipeline mode")
+                        .takes_value(true)
+                        .default_value("default")
+                        .possible_values(&[
+                            "default", 
+                            "isolate", 
+                            "sc", 
+                            "meta", 
+                            "rna", 
+                            "plasmid", 
+                            "bio", 
+                            "corona"
+                        ])
+                        .value_name("MODE")
+                )
+
+                .arg(
+                    Arg::with_name("r1-pattern")
+                        .long("r1-pattern")
+                        .help("Sets a regex to match read 1 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_1_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("r2-pattern")
+                        .long("r2-pattern")
+                        .help("Sets a regex to match read 2 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_2_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("singleton-pattern")
+                        .long("singleton-pattern")
+                        .help("Sets a regex to match singleton file names")
+                        .takes_value(true)
+                        .default_value(finder::SINGLETON_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("duplicate-ids")
+                        .long("duplicate-ids")
+                        .help("Sets how to handle duplicate sample IDs")
+                        .takes_value(true)
+                        .possible_values(&["error", "suffix", "parent"])
+                        .default_value("error")
+                        .value_name("ACTION")
+                )
+
+                .arg(
+                    Arg::with_name("jobs")
+                        .short("j")
+                        .long("jobs")
+                        .help("Sets number of samples to assemble concurrently. Disables the stage progress if more than 1")
+                        .takes_value(true)
+                        .default_value("1")
+                        .value_name("JOB-NUM")
+                )
+
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .help("Skips finished samples and continues unfinished ones")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("auto-clean")
+                        .long("auto-clean")
+                        .help("Cleans unused SPAdes files of each sample right after the assembly")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("keep")
+                        .long("keep")
+                        .help("Keeps files and directories matching the glob patterns when cleaning")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .requires("auto-clean")
+                        .value_name("PATTERN")
+                )
+
+                .arg(
+                    Arg::with_name("decompress")
+                        .long("decompress")
+                        .help("Decompresses bzip2 and zstd reads before running SPAdes")
+                        .takes_value(false)
+                )
+
         )
 
+        .subcommand(
+            App::new("init")
+                .about("Auto find clean reads and writes a config file")
+                .arg(
+                    Arg::with_name("dir")
+                        .short("d")
+                        .long("dir")
+                        .help("Inputs a directory for auto search")
+                        .takes_value(true)
+                        .value_name("CLEAN-READ DIR")
+                        .required(true)
+                )
+
+                .arg(
+                    Arg::with_name("specify")
+                        .short("s")
+                        .long("specify")
+                        .help("Specifies clean read directory names. Accepts glob patterns")
+                        .takes_value(true)
+                        .default_value("trimmed*")
+                        .value_name("DIR NAME")
+                )
+
+                .arg(
+                    Arg::with_name("max-depth")
+                        .long("max-depth")
+                        .help("Limits how deep the auto search goes below the search dir")
+                        .takes_value(true)
+                        .value_name("N")
+                )
+
+                .arg(
+                    Arg::with_name("exclude")
+                        .long("exclude")
+                        .help("Skips directories matching the glob patterns")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("PATTERN")
+                )
+
+                .arg(
+                    Arg::with_name("follow-links")
+                        .long("follow-links")
+                        .help("Follows symbolic links to directories during auto search")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("id-component")
+                        .long("id-component")
+                        .help("Takes sample IDs from the N-th directory below the search dir")
+                        .takes_value(true)
+                        .conflicts_with_all(&["id-parent", "id-regex"])
+                        .value_name("N")
+                )
+
+                .arg(
+                    Arg::with_name("id-parent")
+                        .long("id-parent")
+                        .help("Takes sample IDs from the directory containing the clean reads")
+                        .takes_value(false)
+                        .conflicts_with("id-regex")
+                )
+
+                .arg(
+                    Arg::with_name("id-regex")
+                        .long("id-regex")
+                        .help("Takes sample IDs from the first capture group of a regex on read 1 file names")
+                        .takes_value(true)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .help("Specifies the config file name")
+                        .takes_value(true)
+                        .value_name("CONFIG")
+                        .required(true)
+                )
+
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .help("Sets the config file format")
+                        .takes_value(true)
+                        .possible_values(&["csv", "ini"])
+                        .default_value("csv")
+                        .value_name("FORMAT")
+                )
+
+                .arg(
+                    Arg::with_name("r1-pattern")
+                        .long("r1-pattern")
+                        .help("Sets a regex to match read 1 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_1_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("r2-pattern")
+                        .long("r2-pattern")
+                        .help("Sets a regex to match read 2 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_2_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("singleton-pattern")
+                        .long("singleton-pattern")
+                        .help("Sets a regex to match singleton file names")
+                        .takes_value(true)
+                        .default_value(finder::SINGLETON_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("duplicate-ids")
+                        .long("duplicate-ids")
+                        .help("Sets how to handle duplicate sample IDs")
+                        .takes_value(true)
+                        .possible_values(&["error", "suffix", "parent"])
+                        .default_value("error")
+                        .value_name("ACTION")
+                )
+            )
+
+        .subcommand(
+            App::new("validate")
+                .about("Checks a config file for problems")
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .help("Inputs a config file")
+                        .takes_value(true)
+                        .value_name("INPUT")
+                        .required(true)
+                )
+
+                .arg(
+                    Arg::with_name("r1-pattern")
+                        .long("r1-pattern")
+                        .help("Sets a regex to match read 1 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_1_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("r2-pattern")
+                        .long("r2-pattern")
+                        .help("Sets a regex to match read 2 file names")
+                        .takes_value(true)
+                        .default_value(finder::READ_2_PATTERN)
+                        .value_name("REGEX")
+                )
+
+                .arg(
+                    Arg::with_name("singleton-pattern")
+                        .long("singleton-pattern")
+                        .help("Sets a regex to match singleton file names")
+                        .takes_value(true)
+                        .default_value(finder::SINGLETON_PATTERN)
+                        .value_name("REGEX")
+                )
+            )
+
         .subcommand(
             App::new("clean")
                 .about("Cleans unused SPAdes files.")
@@ -132,63 +581,265 @@ pub fn get_cli(version: &str) {
                         .value_name("DIR")
                         .required(true)
                 )
+
+                .arg(
+                    Arg::with_name("dry")
+                        .long("dry")
+                        .help("Lists the files and directories to remove without removing them")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("keep")
+                        .long("keep")
+                        .help("Keeps files and directories matching the glob patterns")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("PATTERN")
+                )
+
+                .arg(
+                    Arg::with_name("archive")
+                        .long("archive")
+                        .help("Packs the removed files of each sample into <id>_intermediates.tar.gz")
+                        .takes_value(false)
+                )
+
+                .arg(
+                    Arg::with_name("force")
+                        .long("force")
+                        .help("Cleans unfinished or failed SPAdes runs too and overwrites existing archives")
+                        .takes_value(false)
+                )
+            )
+
+        .subcommand(
+            App::new("du")
+                .about("Reports disk usage of SPAdes files")
+                .arg(
+                    Arg::with_name("dir")
+                        .short("d")
+                        .long("dir")
+                        .help("Inputs a directory for the report")
+                        .takes_value(true)
+                        .value_name("DIR")
+                        .required(true)
+                )
+
+                .arg(
+                    Arg::with_name("format")
+                        .long("format")
+                        .help("Sets the report format")
+                        .takes_value(true)
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .value_name("FORMAT")
+                )
+
+                .arg(
+                    Arg::with_name("keep")
+                        .long("keep")
+                        .help("Counts files and directories matching the glob patterns as final outputs")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("PATTERN")
+                )
             )
         
-        .get_matches();
+        .get_matches()
+}
 
+pub fn run(args: &ArgMatches, version: &str) -> Result<(), Error> {
     match args.subcommand() {
         ("auto", Some(clean_matches)) => run_spades_auto(clean_matches, version),
         ("assembly", Some(assembly_matches)) => run_spades(assembly_matches, version),
         ("check", Some(_)) => io::check_dependencies(),
+        ("init", Some(init_matches)) => init_config(init_matches),
+        ("validate", Some(validate_matches)) => validate_config(validate_matches),
         ("clean", Some(clean_matches)) => clean_spades_files(clean_matches),
-        _ => (),
-    };
+        ("du", Some(du_matches)) => report_disk_usage(du_matches),
+        _ => Ok(()),
+    }
+}
+
+// JSON and TSV dry runs should print nothing else to stdout.
+pub fn is_structured_output(args: &ArgMatches) -> bool {
+    match args.subcommand() {
+        ("auto", Some(matches)) | ("assembly", Some(matches)) => {
+            matches.is_present("dry-run") 
+                && is_structured_format(matches)
+        }
+        ("du", Some(matches)) => is_structured_format(matches),
+        _ => false,
+    }
 }
 
-fn run_spades_auto(matches: &ArgMatches, version: &str) {
-    let path = matches.value_of("dir").unwrap();
-    let dirname = matches.value_of("specify").unwrap();
-    let threads = get_thread_num(matches);
-    let dir = get_dir(matches);
-    let args = get_args(matches);
+fn run_spades_auto(matches: &ArgMatches, version: &str) -> Result<(), Error> {
+    let search = get_auto_search(matches)?;
+    let patterns = get_read_patterns(matches)?;
+    let duplicates = get_duplicate_ids(matches)?;
+    let opts = get_runner_opts(matches)?;
     if matches.is_present("dry-run") {
-        io::auto_dryrun(path, &dirname)
+        io::auto_dryrun(&search, &patterns, duplicates, get_output_format(matches)?)
     } else {
         println!("Starting spade-runner v{}...\n", version);
-        io::auto_process_input(path, &dirname, &threads, &dir, &args);
+        io::auto_process_input(&search, &patterns, duplicates, &opts)
     }
 }
 
-fn run_spades(matches: &ArgMatches, version: &str) {
+fn run_spades(matches: &ArgMatches, version: &str) -> Result<(), Error> {
     let path = matches.value_of("input").unwrap();
-    let threads = get_thread_num(matches);
-    let dir = get_dir(matches);
-    let args = get_args(matches);
+    let patterns = get_read_patterns(matches)?;
+    let duplicates = get_duplicate_ids(matches)?;
+    let opts = get_runner_opts(matches)?;
     if matches.is_present("dry-run") {
-        io::dryrun(path)
+        io::dryrun(path, &patterns, duplicates, get_output_format(matches)?)
     } else {
         println!("Starting spade-runner v{}...\n", version);
-        io::process_input(path, &threads, &dir, &args);
+        io::process_input(path, &patterns, duplicates, &opts)
     }
 }
 
-fn clean_spades_files(matches: &ArgMatches) {
+fn init_config(matches: &ArgMatches) -> Result<(), Error> {
+    let search = get_auto_search(matches)?;
+    let output = PathBuf::from(matches.value_of("output").unwrap());
+    let format = ConfigFormat::new(matches.value_of("format").unwrap())?;
+    let patterns = get_read_patterns(matches)?;
+    let duplicates = get_duplicate_ids(matches)?;
+    io::init_config(&search, &patterns, duplicates, &output, format)
+}
+
+fn validate_config(matches: &ArgMatches) -> Result<(), Error> {
+    let input = matches.value_of("input").unwrap();
+    let patterns = get_read_patterns(matches)?;
+    validator::validate_config(input, &patterns)
+}
+
+fn clean_spades_files(matches: &ArgMatches) -> Result<(), Error> {
     let path = PathBuf::from(matches.value_of("dir").unwrap());
-    cleaner::clean_spades_files(&path);
+    let mut opts = CleanOpts::new(matches.is_present("dry"), &get_keep_patterns(matches))?;
+    opts.archive = matches.is_present("archive");
+    opts.force = matches.is_present("force");
+    cleaner::clean_spades_files(&path, &opts)
 }
 
-fn get_thread_num(matches: &ArgMatches) -> Option<usize> {
+fn report_disk_usage(matches: &ArgMatches) -> Result<(), Error> {
+    let path = PathBuf::from(matches.value_of("dir").unwrap());
+    let opts = CleanOpts::new(true, &get_keep_patterns(matches))?;
+    usage::report_disk_usage(&path, &opts, get_output_format(matches)?)
+}
+
+fn get_output_format(matches: &ArgMatches) -> Result<OutputFormat, Error> {
+    OutputFormat::new(matches.value_of("format").unwrap())
+}
+
+fn is_structured_format(matches: &ArgMatches) -> bool {
+    matches!(get_output_format(matches), Ok(format) if format != OutputFormat::Text)
+}
+
+fn get_auto_search(matches: &ArgMatches) -> Result<AutoSearch, Error> {
+    let mut search = AutoSearch::new(
+        matches.value_of("dir").unwrap(), 
+        matches.value_of("specify").unwrap()
+    )?;
+    search.id_source = get_id_source(matches)?;
+    search.follow_links = matches.is_present("follow-links");
+    if let Some(exclude) = matches.values_of("exclude") {
+        search.set_exclude(&exclude.collect::<Vec<&str>>())?;
+    }
+    if let Some(depth) = matches.value_of("max-depth") {
+        search.max_depth = match depth.parse::<usize>() {
+            Ok(depth) => Some(depth),
+            Err(_) => return Err(Error::InvalidInput(format!("INVALID MAX DEPTH: {}", depth))),
+        };
+    }
+
+    Ok(search)
+}
+
+fn get_id_source(matches: &ArgMatches) -> Result<IdSource, Error> {
+    if let Some(num) = matches.value_of("id-component") {
+        return match num.parse::<usize>() {
+            Ok(num) if num > 0 => Ok(IdSource::Component(num)),
+            _ => Err(Error::InvalidInput(format!("INVALID ID COMPONENT: {}. \
+                THE FIRST DIRECTORY BELOW THE SEARCH ROOT IS 1", num))),
+        };
+    }
+
+    if matches.is_present("id-parent") {
+        return Ok(IdSource::Parent);
+    }
+
+    match matches.value_of("id-regex") {
+        Some(pattern) => IdSource::from_regex(pattern),
+        None => Ok(IdSource::default()),
+    }
+}
+
+fn get_duplicate_ids(matches: &ArgMatches) -> Result<DuplicateIds, Error> {
+    DuplicateIds::new(matches.value_of("duplicate-ids").unwrap())
+}
+
+fn get_read_patterns(matches: &ArgMatches) -> Result<ReadPatterns, Error> {
+    ReadPatterns::new(
+        matches.value_of("r1-pattern").unwrap(),
+        matches.value_of("r2-pattern").unwrap(),
+        matches.value_of("singleton-pattern").unwrap(),
+    )
+}
+
+fn get_runner_opts(matches: &ArgMatches) -> Result<RunnerOpts, Error> {
+    let mode = Mode::new(matches.value_of("mode").unwrap())?;
+
+    Ok(RunnerOpts {
+        threads: get_thread_num(matches)?,
+        outdir: get_dir(matches),
+        args: get_args(matches, &mode)?,
+        mode,
+        jobs: get_job_num(matches)?,
+        resume: matches.is_present("resume"),
+        decompress: matches.is_present("decompress"),
+        clean: get_auto_clean_opts(matches)?,
+    })
+}
+
+fn get_auto_clean_opts(matches: &ArgMatches) -> Result<Option<CleanOpts>, Error> {
+    if !matches.is_present("auto-clean") {
+        return Ok(None);
+    }
+
+    Ok(Some(CleanOpts::new(false, &get_keep_patterns(matches))?))
+}
+
+fn get_keep_patterns<'a>(matches: &'a ArgMatches) -> Vec<&'a str> {
+    matches.values_of("keep")
+        .map(|p| p.collect())
+        .unwrap_or_default()
+}
+
+fn get_thread_num(matches: &ArgMatches) -> Result<Option<usize>, Error> {
     let mut threads = None;
 
-    if matches.is_present("threads") {
-        let num = matches.value_of("threads");
-        match num {
-            Some(n) => threads = Some(n.parse::<usize>().unwrap()),
-            None => panic!("INVALID THREAD NUMBERS!"), 
-        }
+    if let Some(num) = matches.value_of("threads") {
+        threads = match num.parse::<usize>() {
+            Ok(num) if num > 0 => Some(num),
+            _ => return Err(Error::InvalidInput(format!("INVALID THREAD NUMBERS: {}. \
+                THREAD NUMBERS SHOULD BE AT LEAST 1", num))),
+        };
     }
 
-    threads
+    Ok(threads)
+}
+
+fn get_job_num(matches: &ArgMatches) -> Result<usize, Error> {
+    let jobs = matches.value_of("jobs").unwrap();
+    match jobs.parse::<usize>() {
+        Ok(jobs) if jobs > 0 => Ok(jobs),
+        _ => Err(Error::InvalidInput(format!("INVALID JOB NUMBERS: {}. \
+            JOB NUMBERS SHOULD BE AT LEAST 1", jobs))),
+    }
 }
 
 fn get_dir(matches: &ArgMatches) -> Option<PathBuf> {
@@ -201,13 +852,12 @@ fn get_dir(matches: &ArgMatches) -> Option<PathBuf> {
     dir
 }
 
-fn get_args(matches: &ArgMatches) -> Option<String> {
-    let mut dir = None;
+fn get_args(matches: &ArgMatches, mode: &Mode) -> Result<Option<Vec<String>>, Error> {
+    let mut args = None;
     if matches.is_present("opts") {
         let input = matches.value_of("opts").unwrap();
-        let args = input.replace("params=", "");
-        dir = Some(String::from(args.trim()));
+        args = Some(params::parse_params(input, mode)?);
     }
 
-    dir
+    Ok(args)
 }
\ No newline at end of file
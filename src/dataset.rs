@@ -0,0 +1,88 @@
+use std::fs;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+use crate::finder::{SeqLib, SeqReads};
+
+// Writes a SPAdes dataset file describing every library of a sample.
+// SPAdes resolves relative paths against the dataset location.
+// Hence, we write the absolute paths.
+pub fn write_dataset(reads: &SeqReads, path: &Path) -> Result<()> {
+    fs::write(path, get_dataset(reads))
+}
+
+fn get_dataset(reads: &SeqReads) -> String {
+    let libs: Vec<String> = reads.libraries.iter()
+        .map(get_library)
+        .collect();
+
+    format!("[\n{}\n]\n", libs.join(",\n"))
+}
+
+fn get_library(lib: &SeqLib) -> String {
+    let mut fields = Vec::new();
+
+    if let Some(orientation) = lib.lib_type.orientation() {
+        fields.push(format!("    orientation: \"{}\"", orientation));
+    }
+
+    fields.push(format!("    type: \"{}\"", lib.lib_type.as_str()));
+
+    if !lib.read_1.is_empty() {
+        fields.push(get_reads("left reads", &lib.read_1));
+    }
+
+    if !lib.read_2.is_empty() {
+        fields.push(get_reads("right reads", &lib.read_2));
+    }
+
+    if !lib.singleton.is_empty() {
+        fields.push(get_reads("single reads", &lib.singleton));
+    }
+
+    format!("  {{\n{}\n  }}", fields.join(",\n"))
+}
+
+fn get_reads(name: &str, reads: &[PathBuf]) -> String {
+    let paths: Vec<String> = reads.iter()
+        .map(|r| format!("      \"{}\"", get_abs_path(r)))
+        .collect();
+
+    format!("    {}: [\n{}\n    ]", name, paths.join(",\n"))
+}
+
+fn get_abs_path(path: &Path) -> String {
+    let path = path.canonicalize().unwrap_or_else(|_| PathBuf::from(path));
+
+    path.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::finder::LibType;
+
+    #[test]
+    fn dataset_test() {
+        let mut pe = SeqLib::new(LibType::PairedEnd);
+        pe.read_1 = vec![PathBuf::from("/reads/a_R1.fq.gz")];
+        pe.read_2 = vec![PathBuf::from("/reads/a_R2.fq.gz")];
+        let mut single = SeqLib::new(LibType::Single);
+        single.singleton = vec![PathBuf::from("/reads/a_single.fq.gz")];
+        let reads = SeqReads {
+            dir: PathBuf::from("/reads"),
+            id: String::from("a"),
+            libraries: vec![pe, single],
+            params: Default::default(),
+        };
+
+        let res = "[\n  {\n    orientation: \"fr\",\n    type: \"paired-end\",\n    \
+            left reads: [\n      \"/reads/a_R1.fq.gz\"\n    ],\n    \
+            right reads: [\n      \"/reads/a_R2.fq.gz\"\n    ]\n  },\n  \
+            {\n    type: \"single\",\n    \
+            single reads: [\n      \"/reads/a_single.fq.gz\"\n    ]\n  }\n]\n";
+        assert_eq!(res, get_dataset(&reads));
+    }
+}
@@ -0,0 +1,86 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Result};
+use std::path::{Path, PathBuf};
+
+use crate::finder::{Compression, SeqReads};
+
+// Decompresses the reads SPAdes cannot read into the output directory.
+// Updates the read paths to the decompressed files.
+// Returns the number of decompressed files.
+pub fn decompress_reads(reads: &mut SeqReads, outdir: &Path) -> Result<usize> {
+    let mut count = 0;
+    for lib in reads.libraries.iter_mut() {
+        for path in lib.files_mut() {
+            let compression = Compression::new(path);
+            if !compression.is_supported() {
+                fs::create_dir_all(outdir)?;
+                let output = get_output_path(path, outdir);
+                decompress(path, &output, &compression)?;
+                *path = output;
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
+pub fn get_unsupported_files(reads: &[SeqReads]) -> Vec<PathBuf> {
+    reads.iter()
+        .flat_map(|r| r.files())
+        .filter(|f| !Compression::new(f).is_supported())
+        .cloned()
+        .collect()
+}
+
+// Drops the compression extension, e.g. sample_R1.fq.bz2 -> sample_R1.fq
+fn get_output_path(path: &Path, outdir: &Path) -> PathBuf {
+    let fname = path.file_stem().unwrap_or_default();
+    outdir.join(fname)
+}
+
+fn decompress(input: &Path, output: &Path, compression: &Compression) -> Result<()> {
+    let file = BufReader::new(File::open(input)?);
+    let mut reader: Box<dyn Read> = match compression {
+        Compression::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        Compression::Zstd => Box::new(zstd::Decoder::new(file)?),
+        _ => Box::new(file),
+    };
+    let mut writer = BufWriter::new(File::create(output)?);
+    io::copy(&mut reader, &mut writer)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn output_path_test() {
+        let path = Path::new("reads/sample_R1.fq.bz2");
+        let outdir = Path::new("assemblies/sample/tmp_reads");
+
+        assert_eq!(outdir.join("sample_R1.fq"), get_output_path(path, outdir));
+    }
+
+    #[test]
+    fn decompress_test() {
+        let outdir = std::env::temp_dir().join("spr_decompress_test");
+        let input = Path::new("test_files/compressed_test");
+        [
+            ("sample_R1.fq.bz2", Compression::Bzip2, "@read_1\nACGTACGTAC\n+\nIIIIIIIIII\n"), 
+            ("sample_R2.fq.zst", Compression::Zstd, "@read_1\nGTACGTACGT\n+\nIIIIIIIIII\n"),
+        ]
+            .iter()
+            .for_each(|(fname, compression, expected)| {
+                let path = input.join(fname);
+                let output = get_output_path(&path, &outdir);
+                fs::create_dir_all(&outdir).unwrap();
+                decompress(&path, &output, compression).unwrap();
+                let res = fs::read_to_string(&output).unwrap();
+                assert_eq!(*expected, res);
+            });
+        fs::remove_dir_all(&outdir).unwrap();
+    }
+}
@@ -0,0 +1,70 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    Config { line: usize, msg: String },
+    MissingReads { id: String, dir: PathBuf },
+    InvalidReads { dir: PathBuf, msg: String, files: Vec<PathBuf> },
+    InvalidInput(String),
+    Spades { id: String, code: Option<i32>, log: PathBuf },
+    FailedSamples(Vec<String>),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Config { line, msg } =>
+                write!(f, "INVALID CONFIG AT LINE {}: {}", line, msg),
+            Error::MissingReads { id, dir } =>
+                write!(f, "NO READS FOUND FOR {} IN {}", id, dir.to_string_lossy()),
+            Error::InvalidReads { dir, msg, files } => {
+                write!(f, "INVALID READS IN {}. {}:", dir.to_string_lossy(), msg)?;
+                files.iter()
+                    .try_for_each(|file| write!(f, "\n  {}", file.to_string_lossy()))
+            }
+            Error::InvalidInput(msg) => write!(f, "{}", msg),
+            Error::Spades { id, code, log } => match code {
+                Some(code) => write!(f, "SPAdes HAS FAILED FOR {} WITH EXIT CODE {}. \
+                    PLEASE CHECK {} FOR DETAILS.", id, code, log.to_string_lossy()),
+                None => write!(f, "SPAdes HAS FAILED FOR {}. \
+                    PLEASE CHECK {} FOR DETAILS.", id, log.to_string_lossy()),
+            },
+            Error::FailedSamples(ids) =>
+                write!(f, "{} SAMPLES FAILED: {:?}", ids.len(), ids),
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn config_error_test() {
+        let err = Error::Config { line: 3, msg: String::from("EXPECTING ID AND DIRECTORY PATH") };
+
+        assert_eq!("INVALID CONFIG AT LINE 3: EXPECTING ID AND DIRECTORY PATH", err.to_string());
+    }
+
+    #[test]
+    fn spades_error_test() {
+        let log = PathBuf::from("assemblies/sample/spades-runner.log");
+        let err = Error::Spades { id: String::from("sample"), code: Some(1), log };
+
+        assert_eq!("SPAdes HAS FAILED FOR sample WITH EXIT CODE 1. \
+            PLEASE CHECK assemblies/sample/spades-runner.log FOR DETAILS.", err.to_string());
+    }
+}
@@ -1,59 +1,464 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::{Result, Write};
+use std::path::{Path, PathBuf};
 
 use glob::{self, MatchOptions};
-use walkdir::WalkDir;
+use lazy_static::lazy_static;
+use regex::Regex;
+use walkdir::{DirEntry, WalkDir};
 
+use crate::error::Error;
+use crate::params::SampleParams;
 use crate::parser::SeqDirs;
 
-pub fn auto_find_cleaned_fastq(path: &str, dirname: &str)  -> Vec<SeqReads> {
-    let mut entries = Vec::new();
+// Default read patterns. These match the Illumina names, e.g. sample_R1_001.fastq.gz,
+// the numbered names, e.g. sample_1.fq.gz, and READ1 used by some pipelines.
+pub const READ_1_PATTERN: &str = r"(?i)(^|[._-])(R1|READ1)([._-]|$)|_1\.f(ast)?q";
+pub const READ_2_PATTERN: &str = r"(?i)(^|[._-])(R2|READ2)([._-]|$)|_2\.f(ast)?q";
+pub const SINGLETON_PATTERN: &str = r"(?i)singleton|unpaired";
 
-    WalkDir::new(path).into_iter()
+pub struct ReadPatterns {
+    read_1: Regex,
+    read_2: Regex,
+    singleton: Regex,
+}
+
+impl ReadPatterns {
+    pub fn new(read_1: &str, read_2: &str, singleton: &str) -> std::result::Result<Self, Error> {
+        Ok(Self {
+            read_1: get_regex(read_1)?,
+            read_2: get_regex(read_2)?,
+            singleton: get_regex(singleton)?,
+        })
+    }
+}
+
+impl Default for ReadPatterns {
+    fn default() -> Self {
+        Self::new(READ_1_PATTERN, READ_2_PATTERN, SINGLETON_PATTERN)
+            .expect("INVALID DEFAULT READ PATTERNS")
+    }
+}
+
+fn get_regex(pattern: &str) -> std::result::Result<Regex, Error> {
+    Regex::new(pattern)
+        .map_err(|e| Error::InvalidInput(format!("INVALID READ PATTERN {}: {}", pattern, e)))
+}
+
+// Samples with matching reads and the samples we could not match.
+// A failed sample does not stop the batch.
+pub struct Samples {
+    pub reads: Vec<SeqReads>,
+    pub failed: Vec<(String, Error)>,
+}
+
+impl Samples {
+    fn new() -> Self {
+        Self {
+            reads: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    // Samples with the same ID would write to the same output directory.
+    // Hence, we check them before running anything.
+    pub fn resolve_duplicates(&mut self, action: DuplicateIds) -> std::result::Result<(), Error> {
+        self.check_duplicate_files()?;
+        match action {
+            DuplicateIds::Error => (),
+            DuplicateIds::Suffix => self.add_id_suffix(),
+            DuplicateIds::Parent => self.add_parent_ids(),
+        }
+
+        self.check_duplicate_ids(action)
+    }
+
+    // Sample indexes sharing the same ID, sorted by the directory.
+    fn get_duplicate_ids(&self) -> Vec<Vec<usize>> {
+        let mut ids: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        self.reads.iter()
+            .enumerate()
+            .for_each(|(i, r)| ids.entry(&r.id).or_default().push(i));
+
+        ids.into_values()
+            .filter(|idx| idx.len() > 1)
+            .map(|mut idx| {
+                idx.sort_by(|a, b| self.reads[*a].dir.cmp(&self.reads[*b].dir));
+                idx
+            })
+            .collect()
+    }
+
+    // Keeps the first ID and numbers the rest, e.g. sample, sample_2.
+    fn add_id_suffix(&mut self) {
+        let mut used: HashSet<String> = self.reads.iter()
+            .map(|r| r.id.clone())
+            .collect();
+        self.get_duplicate_ids().iter()
+            .for_each(|idx| {
+                let mut num = 2;
+                idx.iter().skip(1)
+                    .for_each(|&i| {
+                        let id = &self.reads[i].id;
+                        while used.contains(&format!("{}_{}", id, num)) {
+                            num += 1;
+                        }
+                        let id = format!("{}_{}", id, num);
+                        used.insert(id.clone());
+                        self.reads[i].id = id;
+                    });
+            });
+    }
+
+    // Adds the name of the directory above the reads, e.g. sample/run_2/trimmed
+    // becomes sample_run_2. Keeps the ID if the parent is the sample directory.
+    fn add_parent_ids(&mut self) {
+        self.get_duplicate_ids().iter()
+            .flatten()
+            .for_each(|&i| {
+                let reads = &mut self.reads[i];
+                let parent = reads.dir.parent()
+                    .and_then(|p| p.file_name())
+                    .map(|p| p.to_string_lossy().to_string());
+                if let Some(parent) = parent {
+                    if parent != reads.id {
+                        reads.id = format!("{}_{}", reads.id, parent);
+                    }
+                }
+            });
+    }
+
+    fn check_duplicate_ids(&self, action: DuplicateIds) -> std::result::Result<(), Error> {
+        let duplicates: Vec<String> = self.get_duplicate_ids().iter()
+            .map(|idx| {
+                let dirs: Vec<String> = idx.iter()
+                    .map(|&i| self.reads[i].dir.to_string_lossy().to_string())
+                    .collect();
+                format!("  {}: {}", self.reads[idx[0]].id, dirs.join(", "))
+            })
+            .collect();
+
+        if duplicates.is_empty() {
+            return Ok(());
+        }
+
+        let hint = match action {
+            DuplicateIds::Error => "USE --duplicate-ids suffix OR parent TO RENAME THEM",
+            _ => "PLEASE RENAME THEM USING A CONFIG FILE",
+        };
+
+        Err(Error::InvalidInput(format!("DUPLICATE SAMPLE IDS. {}:\n{}", 
+            hint, duplicates.join("\n"))))
+    }
+
+    // The same reads in two samples usually means overlapping search directories.
+    fn check_duplicate_files(&self) -> std::result::Result<(), Error> {
+        let mut files: BTreeMap<PathBuf, Vec<&str>> = BTreeMap::new();
+        self.reads.iter()
+            .for_each(|r| {
+                r.files()
+                    .for_each(|f| {
+                        let path = f.canonicalize().unwrap_or_else(|_| f.clone());
+                        files.entry(path).or_default().push(&r.id);
+                    })
+            });
+
+        let duplicates: Vec<String> = files.iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(f, ids)| format!("  {}: {}", f.to_string_lossy(), ids.join(", ")))
+            .collect();
+
+        if !duplicates.is_empty() {
+            return Err(Error::InvalidInput(format!("THE SAME READ FILES ARE USED \
+                BY MORE THAN ONE SAMPLE:\n{}", duplicates.join("\n"))));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateIds {
+    Error,
+    Suffix,
+    Parent,
+}
+
+impl DuplicateIds {
+    pub fn new(action: &str) -> std::result::Result<Self, Error> {
+        match action {
+            "error" => Ok(DuplicateIds::Error),
+            "suffix" => Ok(DuplicateIds::Suffix),
+            "parent" => Ok(DuplicateIds::Parent),
+            _ => Err(Error::InvalidInput(format!("UNSUPPORTED DUPLICATE ID ACTION: {}", action))),
+        }
+    }
+}
+
+// Where auto search takes the sample IDs from.
+pub enum IdSource {
+    // The N-th directory below the search root, starting from 1.
+    Component(usize),
+    // The directory containing the clean read directory.
+    Parent,
+    // The first capture group of a regex on the read 1 file name.
+    Regex(Regex),
+}
+
+impl IdSource {
+    pub fn from_regex(pattern: &str) -> std::result::Result<Self, Error> {
+        let re = Regex::new(pattern)
+            .map_err(|e| Error::InvalidInput(format!("INVALID ID PATTERN {}: {}", pattern, e)))?;
+
+        Ok(IdSource::Regex(re))
+    }
+
+    fn as_string(&self) -> String {
+        match self {
+            IdSource::Component(n) => format!("DIRECTORY {} BELOW THE SEARCH ROOT", n),
+            IdSource::Parent => String::from("THE PARENT DIRECTORY"),
+            IdSource::Regex(re) => format!("PATTERN {} ON THE READ 1 FILE NAME", re.as_str()),
+        }
+    }
+}
+
+// The first directory below the root is the sample directory,
+// e.g. clean_reads/sample/trimmed.
+impl Default for IdSource {
+    fn default() -> Self {
+        IdSource::Component(1)
+    }
+}
+
+pub struct AutoSearch {
+    pub root: PathBuf,
+    // Matches the clean read directory's own name, e.g. trimmed or trimmed*.
+    pub dirname: glob::Pattern,
+    pub id_source: IdSource,
+    pub max_depth: Option<usize>,
+    pub exclude: Vec<glob::Pattern>,
+    pub follow_links: bool,
+}
+
+impl AutoSearch {
+    pub fn new(root: &str, dirname: &str) -> std::result::Result<Self, Error> {
+        Ok(Self {
+            root: PathBuf::from(root),
+            dirname: get_dir_pattern(dirname)?,
+            id_source: IdSource::default(),
+            max_depth: None,
+            exclude: Vec::new(),
+            follow_links: false,
+        })
+    }
+
+    pub fn set_exclude(&mut self, patterns: &[&str]) -> std::result::Result<(), Error> {
+        self.exclude = patterns.iter()
+            .map(|p| get_dir_pattern(p))
+            .collect::<std::result::Result<Vec<_>, Error>>()?;
+
+        Ok(())
+    }
+
+    fn is_match(&self, entry: &DirEntry) -> bool {
+        entry.file_type().is_dir()
+            && self.dirname.matches(&entry.file_name().to_string_lossy())
+    }
+
+    // Exclude patterns match the directory name
+    // or its path below the search root, e.g. old_runs/*/trimmed.
+    fn is_excluded(&self, entry: &DirEntry) -> bool {
+        if entry.depth() == 0 {
+            return false;
+        }
+        let name = entry.file_name().to_string_lossy();
+        let path = entry.path().strip_prefix(&self.root).unwrap_or_else(|_| entry.path());
+        self.exclude.iter()
+            .any(|p| p.matches(&name) || p.matches_path(path))
+    }
+}
+
+fn get_dir_pattern(pattern: &str) -> std::result::Result<glob::Pattern, Error> {
+    glob::Pattern::new(pattern)
+        .map_err(|e| Error::InvalidInput(format!("INVALID DIRECTORY PATTERN {}: {}", pattern, e)))
+}
+
+pub fn auto_find_cleaned_fastq(search: &AutoSearch, patterns: &ReadPatterns) -> Samples {
+    let mut entries = Samples::new();
+    // Sorted to keep the sample order and the duplicate IDs the same between runs.
+    let mut walker = WalkDir::new(&search.root)
+        .follow_links(search.follow_links)
+        .sort_by_file_name();
+    if let Some(depth) = search.max_depth {
+        walker = walker.max_depth(depth);
+    }
+
+    walker.into_iter()
+        .filter_entry(|e| !search.is_excluded(e))
         .filter_map(|ok| ok.ok())
-        .filter(|e| e.file_type().is_dir())
+        .filter(|e| search.is_match(e))
         .for_each(|e| {
             let dir = e.path().to_string_lossy();
-            if dir.contains(dirname) {
-                let target = None;
-                get_cleaned_fastq(&dir, &mut entries, target);
-            }
+            get_auto_fastq(&dir, &mut entries, search, patterns);
         }); 
     
     entries                    
 }
 
-pub fn find_cleaned_fastq(dirs: &[SeqDirs])  -> Vec<SeqReads> {
-    let mut entries = Vec::new();
+// Auto mode walks through every matching directory.
+// Hence, we skip directories without reads.
+fn get_auto_fastq(dir: &str, entries: &mut Samples, search: &AutoSearch, patterns: &ReadPatterns) {
+    let mut files = SeqReads::new(dir);
+    let res = files.find_reads(patterns);
+    if res.is_ok() && files.libraries.is_empty() {
+        return;
+    }
+
+    let id = files.get_auto_id(search);
+    match (res, id) {
+        (Ok(()), Ok(())) => entries.reads.push(files),
+        (Err(e), Ok(())) => entries.failed.push((files.id, e)),
+        (Err(e), Err(_)) | (Ok(()), Err(e)) => entries.failed.push((dir.to_string(), e)),
+    }
+}
+
+pub fn find_cleaned_fastq(dirs: &[SeqDirs], patterns: &ReadPatterns) -> Samples {
+    let mut entries = Samples::new();
 
     dirs.iter()
-        .for_each(|s| {
-            get_cleaned_fastq(&s.dir, &mut entries, Some(s.id.clone()))
+        .for_each(|s| match s.read_1 {
+            Some(_) => get_listed_reads(s, &mut entries),
+            None => get_cleaned_fastq(s, &mut entries, patterns),
         });
     
     entries
 }
 
-fn get_cleaned_fastq(
-    dir: &str, 
-    entries: &mut Vec<SeqReads>, 
-    target: Option<String>
-) {
-    let mut files = SeqReads::new(&dir);
-    let fastq = files.glob_fastq();
-    files.match_reads(&fastq);
-    files.get_id(target);
+// Reads listed in the sample sheet are used as they are.
+fn get_listed_reads(sample: &SeqDirs, entries: &mut Samples) {
+    match SeqReads::from_seqdir(sample) {
+        Ok(reads) => entries.reads.push(reads),
+        Err(e) => entries.failed.push((sample.id.clone(), e)),
+    }
+}
 
-    if !files.read_1.as_os_str().is_empty() {
-        entries.push(files);
+// The directories listed in a config must have reads.
+fn get_cleaned_fastq(sample: &SeqDirs, entries: &mut Samples, patterns: &ReadPatterns) {
+    let mut files = SeqReads::new(&sample.dir);
+    files.id = sample.id.clone();
+    files.params = sample.params.clone();
+
+    match files.find_reads(patterns) {
+        Ok(()) if !files.libraries.is_empty() => entries.reads.push(files),
+        Ok(()) => entries.failed.push((files.id.clone(), 
+            Error::MissingReads { id: files.id, dir: PathBuf::from(&sample.dir) })),
+        Err(e) => entries.failed.push((files.id, e)),
     }
 }
 
+lazy_static! {
+    // FASTQ files, uncompressed or compressed.
+    static ref SEQ_EXTENSIONS: Regex = 
+        Regex::new(r"(?i)\.f(ast)?q(\.(gz|gzip|bz2|zst))?$").unwrap();
+}
+
+fn is_sequence_file(path: &Path) -> bool {
+    path.is_file() && SEQ_EXTENSIONS.is_match(&path.to_string_lossy())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Compression {
+    Uncompressed,
+    Gzip,
+    Bzip2,
+    Zstd,
+}
+
+impl Compression {
+    pub fn new(path: &Path) -> Self {
+        let ext = path.extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        match ext.as_str() {
+            "gz" | "gzip" => Compression::Gzip,
+            "bz2" => Compression::Bzip2,
+            "zst" => Compression::Zstd,
+            _ => Compression::Uncompressed,
+        }
+    }
+
+    // SPAdes reads uncompressed and gzipped files.
+    pub fn is_supported(&self) -> bool {
+        matches!(self, Compression::Uncompressed | Compression::Gzip)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LibType {
+    PairedEnd,
+    MatePairs,
+    Single,
+}
+
+impl LibType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            LibType::PairedEnd => "paired-end",
+            LibType::MatePairs => "mate-pairs",
+            LibType::Single => "single",
+        }
+    }
+
+    pub fn orientation(&self) -> Option<&str> {
+        match self {
+            LibType::PairedEnd => Some("fr"),
+            LibType::MatePairs => Some("rf"),
+            LibType::Single => None,
+        }
+    }
+}
+
+// A sequencing library. Each read may span multiple files,
+// such as the same library sequenced in several lanes.
+#[derive(Clone)]
+pub struct SeqLib {
+    pub lib_type: LibType,
+    pub read_1: Vec<PathBuf>,
+    pub read_2: Vec<PathBuf>,
+    pub singleton: Vec<PathBuf>,
+}
+
+impl SeqLib {
+    pub fn new(lib_type: LibType) -> Self {
+        Self {
+            lib_type,
+            read_1: Vec::new(),
+            read_2: Vec::new(),
+            singleton: Vec::new(),
+        }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.read_1.iter()
+            .chain(self.read_2.iter())
+            .chain(self.singleton.iter())
+    }
+
+    pub fn files_mut(&mut self) -> impl Iterator<Item = &mut PathBuf> {
+        self.read_1.iter_mut()
+            .chain(self.read_2.iter_mut())
+            .chain(self.singleton.iter_mut())
+    }
+}
+
+#[derive(Clone)]
 pub struct SeqReads {
     pub dir: PathBuf,
     pub id: String, 
-    pub read_1: PathBuf,
-    pub read_2: PathBuf,
-    pub singleton: Option<PathBuf>
+    pub libraries: Vec<SeqLib>,
+    pub params: SampleParams,
 }
 
 impl SeqReads {
@@ -61,50 +466,338 @@ impl SeqReads {
         Self {
             dir: PathBuf::from(dir),
             id: String::new(),
-            read_1: PathBuf::new(),
-            read_2: PathBuf::new(),
-            singleton: None,
+            libraries: Vec::new(),
+            params: SampleParams::default(),
+        }
+    }
+
+    // Relative read paths are relative to the sample directory, if any.
+    fn from_seqdir(sample: &SeqDirs) -> std::result::Result<Self, Error> {
+        let get_path = |path: &str| {
+            let path = PathBuf::from(path);
+            if path.is_relative() && !sample.dir.is_empty() {
+                Path::new(&sample.dir).join(path)
+            } else {
+                path
+            }
+        };
+
+        let mut lib = match (&sample.read_1, &sample.read_2) {
+            (Some(r1), Some(r2)) => {
+                let mut lib = SeqLib::new(LibType::PairedEnd);
+                lib.read_1.push(get_path(r1));
+                lib.read_2.push(get_path(r2));
+                lib
+            }
+            (Some(r1), None) => {
+                let mut lib = SeqLib::new(LibType::Single);
+                lib.singleton.push(get_path(r1));
+                lib
+            }
+            _ => return Err(Error::InvalidInput(format!("NO READ 1 FOR {}", sample.id))),
+        };
+
+        if let Some(singleton) = &sample.singleton {
+            lib.singleton.push(get_path(singleton));
+        }
+
+        let dir = if sample.dir.is_empty() {
+            lib.files().next()
+                .and_then(|f| f.parent())
+                .map(|p| p.to_path_buf())
+                .unwrap_or_default()
+        } else {
+            PathBuf::from(&sample.dir)
+        };
+
+        let missing: Vec<PathBuf> = lib.files()
+            .filter(|f| !f.is_file())
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(Error::InvalidReads {
+                dir,
+                msg: String::from("READ FILES NOT FOUND"),
+                files: missing,
+            });
+        }
+
+        Ok(Self {
+            dir,
+            id: sample.id.clone(),
+            libraries: vec![lib],
+            params: sample.params.clone(),
+        })
+    }
+
+    // A single paired-end library with one file per read.
+    // SPAdes can take it without a dataset file.
+    pub fn is_single_pair(&self) -> bool {
+        match self.libraries.as_slice() {
+            [lib] => lib.lib_type == LibType::PairedEnd 
+                && lib.read_1.len() == 1 
+                && lib.read_2.len() == 1
+                && lib.singleton.len() <= 1,
+            _ => false,
+        }
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.libraries.iter().flat_map(|lib| lib.files())
+    }
+
+    pub fn is_single_library(&self) -> bool {
+        self.libraries.len() == 1 
+            && self.libraries[0].lib_type == LibType::PairedEnd
+    }
+
+    // Problems that do not stop the run, but are worth checking.
+    pub fn get_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        self.files()
+            .for_each(|f| {
+                match File::open(f).and_then(|file| file.metadata()) {
+                    Ok(meta) if meta.len() == 0 => 
+                        warnings.push(format!("EMPTY FILE: {}", f.to_string_lossy())),
+                    Err(_) => warnings.push(format!("CAN'T READ FILE: {}", f.to_string_lossy())),
+                    _ => (),
+                }
+                if !Compression::new(f).is_supported() {
+                    warnings.push(format!("SPAdes CAN'T READ BZIP2 OR ZSTD FILES. \
+                        USE --decompress FOR: {}", f.to_string_lossy()));
+                }
+            });
+
+        if self.libraries.iter().all(|lib| lib.lib_type == LibType::Single) {
+            warnings.push(String::from("NO READ 2 FOUND. ASSEMBLING AS SINGLE-END READS"));
         }
+
+        warnings
     }
 
-    fn glob_fastq(&self) -> Vec<PathBuf> {
-        let pattern = format!("{}/*.f*.g*", 
-            self.dir.to_string_lossy());
+    pub fn write_reads<W: Write>(&self, handle: &mut W) -> Result<()> {
+        let multi_lib = self.libraries.len() > 1;
+        for (i, lib) in self.libraries.iter().enumerate() {
+            if multi_lib || !self.is_single_pair() {
+                writeln!(handle, "Library {}\t: {}", i + 1, lib.lib_type.as_str())?;
+            }
+            lib.read_1.iter()
+                .try_for_each(|r| writeln!(handle, "Read 1\t\t: {}", r.to_string_lossy()))?;
+            lib.read_2.iter()
+                .try_for_each(|r| writeln!(handle, "Read 2\t\t: {}", r.to_string_lossy()))?;
+            lib.singleton.iter()
+                .try_for_each(|r| writeln!(handle, "Singleton\t: {}", r.to_string_lossy()))?;
+        }
+
+        Ok(())
+    }
+
+    fn find_reads(&mut self, patterns: &ReadPatterns) -> std::result::Result<(), Error> {
+        let fastq = self.glob_fastq()?;
+        self.match_reads(&fastq, patterns)
+    }
+
+    fn glob_fastq(&self) -> std::result::Result<Vec<PathBuf>, Error> {
+        let pattern = format!("{}/*.f*", 
+            glob::Pattern::escape(&self.dir.to_string_lossy()));
     
         let opts = MatchOptions {
             case_sensitive: true,
             ..Default::default()
         };
     
-        glob::glob_with(&pattern, opts)
-            .unwrap()
+        let mut files: Vec<PathBuf> = glob::glob_with(&pattern, opts)
+            .map_err(|e| Error::InvalidInput(format!("INVALID DIR {}: {}", 
+                self.dir.to_string_lossy(), e)))?
             .filter_map(|ok| ok.ok())
-            .collect()
+            .filter(|e| is_sequence_file(e))
+            .collect();
+        files.sort();
+
+        Ok(files)
     }
 
-    fn match_reads(&mut self, dirs: &[PathBuf]) {
+    // Only matches the file names. 
+    // The directory names may contain R1 or R2, e.g. PR1/.
+    fn match_reads(
+        &mut self, 
+        dirs: &[PathBuf], 
+        patterns: &ReadPatterns
+    ) -> std::result::Result<(), Error> {
+        let mut read_1: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        let mut read_2: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+        let mut singleton = Vec::new();
+        let mut ambiguous = Vec::new();
+
         dirs.iter()
             .for_each(|e| {
-                match e.to_string_lossy().to_uppercase() {
-                    d if d.contains("READ1") => self.read_1 = PathBuf::from(e),
-                    d if d.contains("R1") => self.read_1 = PathBuf::from(e),
-                    d if d.contains("READ2") => self.read_2 = PathBuf::from(e),
-                    d if d.contains("R2") => self.read_2 = PathBuf::from(e),
-                    d if d.contains("SINGLETON") => self.singleton = Some(PathBuf::from(e)),
-                    _ => (),
+                let fname = e.file_name().unwrap_or_default().to_string_lossy();
+                let matches = (
+                    patterns.read_1.is_match(&fname), 
+                    patterns.read_2.is_match(&fname), 
+                    patterns.singleton.is_match(&fname)
+                );
+                // Singleton names often contain the read number too, 
+                // e.g. sample_R1_unpaired.fq.gz from Trimmomatic.
+                match matches {
+                    (_, _, true) => singleton.push(e.clone()),
+                    (true, false, false) => read_1.entry(get_library_key(&fname, &patterns.read_1))
+                        .or_default()
+                        .push(e.clone()),
+                    (false, true, false) => read_2.entry(get_library_key(&fname, &patterns.read_2))
+                        .or_default()
+                        .push(e.clone()),
+                    (false, false, false) => (),
+                    (true, true, false) => ambiguous.push(e.clone()),
                 }
             });
+
+        if !ambiguous.is_empty() {
+            return Err(Error::InvalidReads {
+                dir: self.dir.clone(),
+                msg: String::from("AMBIGUOUS READ FILES. THE FILES MATCH MORE THAN ONE READ PATTERN"),
+                files: ambiguous,
+            });
+        }
+
+        self.check_pairs(&read_1, &read_2)?;
+        self.get_libraries(read_1, read_2, singleton);
+
+        Ok(())
     }
 
-    fn get_id(&mut self, target: Option<String>) {
-        if target.is_none() {
-            let dirs: Vec<_> = self.dir.components().map(|d| d.as_os_str()).collect();
-            assert!(dirs.len() > 1, "INVALID FOLDER STRUCTURE TO USE AUTO");
-            self.id = String::from(dirs[1].to_string_lossy());
-        } else {
-            self.id = String::from(target.as_ref().unwrap());
+    // Each read 1 should have a matching read 2 with the same library key.
+    // Read 1 without read 2 is only allowed when the directory contains no read 2,
+    // i.e. single-end sequencing.
+    fn check_pairs(
+        &self, 
+        read_1: &BTreeMap<String, Vec<PathBuf>>, 
+        read_2: &BTreeMap<String, Vec<PathBuf>>
+    ) -> std::result::Result<(), Error> {
+        if read_2.is_empty() {
+            return Ok(());
+        }
+
+        let is_paired = read_1.len() == read_2.len() 
+            && read_1.iter()
+                .all(|(key, r1)| read_2.get(key).map(|r2| r2.len()) == Some(r1.len()));
+
+        if !is_paired {
+            let mut candidates: Vec<PathBuf> = read_1.values()
+                .chain(read_2.values())
+                .flatten()
+                .cloned()
+                .collect();
+            candidates.sort();
+            return Err(Error::InvalidReads {
+                dir: self.dir.clone(),
+                msg: String::from("MULTIPLE OR UNPAIRED R1/R2 CANDIDATES. \
+                    PLEASE CHECK THE FILES OR THE READ PATTERNS"),
+                files: candidates,
+            });
+        }
+
+        Ok(())
+    }
+
+    // Pairs read 1 and read 2 files sharing the same library key.
+    // Singletons go to the first paired-end library.
+    // Mate-pairs have an MP or MATE token next to the read tag, e.g. sample_MP_R1.fq.gz.
+    fn get_libraries(
+        &mut self,
+        read_1: BTreeMap<String, Vec<PathBuf>>,
+        mut read_2: BTreeMap<String, Vec<PathBuf>>,
+        mut singleton: Vec<PathBuf>
+    ) {
+        read_1.into_iter()
+            .for_each(|(key, mut r1)| {
+                let lib_type = match read_2.get(&key) {
+                    Some(_) if MATE_PAIRS.is_match(&key) => LibType::MatePairs,
+                    Some(_) => LibType::PairedEnd,
+                    None => LibType::Single,
+                };
+                let mut lib = SeqLib::new(lib_type);
+                r1.sort();
+                if lib_type == LibType::Single {
+                    lib.singleton = r1;
+                } else {
+                    let mut r2 = read_2.remove(&key).unwrap();
+                    r2.sort();
+                    lib.read_1 = r1;
+                    lib.read_2 = r2;
+                }
+                self.libraries.push(lib);
+            });
+
+        // Paired-end libraries come first, then mate-pairs and single reads.
+        self.libraries.sort_by_key(|lib| lib.lib_type as u8);
+        if !singleton.is_empty() {
+            singleton.sort();
+            match self.libraries.iter_mut().find(|l| l.lib_type == LibType::PairedEnd) {
+                Some(lib) => lib.singleton = singleton,
+                None => if !self.libraries.is_empty() {
+                    let mut lib = SeqLib::new(LibType::Single);
+                    lib.singleton = singleton;
+                    self.libraries.push(lib);
+                },
+            }
+        }
+    }
+
+    fn get_auto_id(&mut self, search: &AutoSearch) -> std::result::Result<(), Error> {
+        let id = match &search.id_source {
+            IdSource::Component(n) => self.dir.strip_prefix(&search.root).ok()
+                .and_then(|dir| dir.components().nth(n.saturating_sub(1)))
+                .map(|dir| dir.as_os_str().to_string_lossy().to_string()),
+            IdSource::Parent => self.dir.parent()
+                .and_then(|dir| dir.file_name())
+                .map(|dir| dir.to_string_lossy().to_string()),
+            IdSource::Regex(re) => self.get_read_1_name()
+                .and_then(|fname| {
+                    let caps = re.captures(&fname)?;
+                    caps.get(1).or_else(|| caps.get(0)).map(|m| m.as_str().to_string())
+                }),
+        };
+
+        match id {
+            Some(id) if !id.is_empty() => {
+                self.id = id;
+                Ok(())
+            }
+            _ => Err(Error::InvalidInput(format!("CAN'T GET THE SAMPLE ID OF {} FROM {}", 
+                self.dir.to_string_lossy(), search.id_source.as_string()))),
         }
     }
+
+    // Single-end reads are stored as singletons.
+    fn get_read_1_name(&self) -> Option<String> {
+        self.libraries.iter()
+            .flat_map(|lib| lib.read_1.iter())
+            .chain(self.files())
+            .next()
+            .and_then(|f| f.file_name())
+            .map(|f| f.to_string_lossy().to_string())
+    }
+}
+
+lazy_static! {
+    // Matches the library key, where * replaces the read tag.
+    static ref MATE_PAIRS: Regex = 
+        Regex::new(r"(^|[_.-])(MP|mp|MATE|mate)[_.-]?\*|\*[_.-]?(MP|mp|MATE|mate)([_.-]|$)").unwrap();
+    static ref LANE: Regex = Regex::new(r"(?i)_L\d{3}").unwrap();
+    static ref COMPRESSION: Regex = Regex::new(r"(?i)\.(gz|gzip|bz2|zst)$").unwrap();
+}
+
+// Removes the lane, the read number, and the compression from the file name.
+// Files with the same key belong to the same library.
+fn get_library_key(fname: &str, read: &Regex) -> String {
+    let key = LANE.replace_all(fname, "");
+    let key = COMPRESSION.replace(&key, "").to_string();
+
+    match read.find_iter(&key).last() {
+        Some(m) => format!("{}*{}", &key[..m.start()], &key[m.end()..]),
+        None => key,
+    }
 }
 
 #[cfg(test)]
@@ -117,34 +810,77 @@ mod test {
 
         let seq = SeqReads::new(&input);
 
-        let res = seq.glob_fastq();
+        let res = seq.glob_fastq().unwrap();
         assert_eq!(2, res.len());
     }
 
     #[test]
     fn find_cleaned_fastq_test() {
         let input = "test_files/";
-        let dirname = "trimmed";
+        let dirname = "trimmed*";
 
-        let res = auto_find_cleaned_fastq(&input, &dirname);
+        let res = auto_find_cleaned_fastq(&AutoSearch::new(input, dirname).unwrap(), &ReadPatterns::default());
 
-        assert_eq!(1, res.len());
+        assert_eq!(1, res.reads.len());
+    }
+
+    #[test]
+    fn auto_search_match_test() {
+        let find = |search: &AutoSearch| {
+            auto_find_cleaned_fastq(search, &ReadPatterns::default()).reads.len()
+        };
+        let mut search = AutoSearch::new("test_files", "trimmed").unwrap();
+        assert_eq!(0, find(&search));
+
+        // The default pattern skips untrimmed_test.
+        search.dirname = glob::Pattern::new("trimmed*").unwrap();
+        let res = auto_find_cleaned_fastq(&search, &ReadPatterns::default());
+        assert_eq!(1, res.reads.len());
+        assert_eq!(PathBuf::from("test_files/trimmed_test"), res.reads[0].dir);
+
+        search.dirname = glob::Pattern::new("trimmed_test").unwrap();
+        assert_eq!(1, find(&search));
+
+        search.set_exclude(&["trimmed_*"]).unwrap();
+        assert_eq!(0, find(&search));
+
+        search.set_exclude(&[]).unwrap();
+        search.max_depth = Some(0);
+        assert_eq!(0, find(&search));
+    }
+
+    #[test]
+    fn auto_search_order_test() {
+        let root = std::env::temp_dir().join("spr_auto_order_test");
+        ["c", "a", "b"].iter()
+            .for_each(|id| {
+                let dir = root.join(id).join("trimmed");
+                std::fs::create_dir_all(&dir).unwrap();
+                std::fs::write(dir.join("reads_R1.fq.gz"), "").unwrap();
+                std::fs::write(dir.join("reads_R2.fq.gz"), "").unwrap();
+            });
+        let search = AutoSearch::new(&root.to_string_lossy(), "trimmed").unwrap();
+        let res = auto_find_cleaned_fastq(&search, &ReadPatterns::default());
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let ids: Vec<&str> = res.reads.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(vec!["a", "b", "c"], ids);
     }
 
     #[test]
     fn find_cleaned_fastq_reads_test() {
         let input = "test_files/";
-        let dirname = "trimmed";
+        let dirname = "trimmed_test";
 
-        let res = auto_find_cleaned_fastq(&input, &dirname);
+        let res = auto_find_cleaned_fastq(&AutoSearch::new(input, dirname).unwrap(), &ReadPatterns::default());
 
         let path = PathBuf::from(input).join("trimmed_test");
         let r1 = path.join("some_seq_ABC123_R1.fq.gz");
         let r2 = path.join("some_seq_ABC123_R2.fq.gz");
-        res.iter()
+        res.reads.iter()
             .for_each(|e| {
-                assert_eq!(r1, e.read_1);
-                assert_eq!(r2, e.read_2);
+                assert_eq!(vec![r1.clone()], e.libraries[0].read_1);
+                assert_eq!(vec![r2.clone()], e.libraries[0].read_2);
                 assert_eq!(String::from("trimmed_test"), e.id);
             })
     }
@@ -152,16 +888,272 @@ mod test {
     #[test]
     fn get_cleaned_fastq_test() {
         let dir = "test_files/trimmed_test";
-        let mut res = Vec::new();
+        let mut res = Samples::new();
 
-        get_cleaned_fastq(&dir, &mut res, None);
+        let search = AutoSearch::new("test_files", "trimmed").unwrap();
+        get_auto_fastq(dir, &mut res, &search, &ReadPatterns::default());
         let path = PathBuf::from(dir);
         let r1 = path.join("some_seq_ABC123_R1.fq.gz");
         let r2 = path.join("some_seq_ABC123_R2.fq.gz");
-        res.iter()
+
+        assert_eq!(1, res.reads.len());
+        res.reads.iter()
             .for_each(|e| {
-                assert_eq!(r1, e.read_1);
-                assert_eq!(r2, e.read_2);
+                assert_eq!(vec![r1.clone()], e.libraries[0].read_1);
+                assert_eq!(vec![r2.clone()], e.libraries[0].read_2);
             })
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn auto_id_test() {
+        let get_id = |root: &str, source: IdSource| {
+            let mut reads = SeqReads::new("/data/clean_reads/sample/run_1/trimmed");
+            let mut lib = SeqLib::new(LibType::PairedEnd);
+            lib.read_1.push(reads.dir.join("Sample-1_S12_L001_R1_001.fastq.gz"));
+            reads.libraries.push(lib);
+            let mut search = AutoSearch::new(root, "trimmed").unwrap();
+            search.id_source = source;
+            reads.get_auto_id(&search).map(|_| reads.id)
+        };
+
+        assert_eq!("sample", get_id("/data/clean_reads", IdSource::default()).unwrap());
+        assert_eq!("run_1", get_id("/data/clean_reads/", IdSource::Component(2)).unwrap());
+        assert_eq!("run_1", get_id("/data", IdSource::Parent).unwrap());
+        assert_eq!("Sample-1", get_id("/data", IdSource::from_regex(r"^(.+)_S\d+_L").unwrap()).unwrap());
+        assert!(get_id("/data/clean_reads", IdSource::Component(4)).is_err());
+        assert!(get_id("/data", IdSource::from_regex(r"^(.+)_R2").unwrap()).is_err());
+    }
+
+    #[test]
+    fn sequence_extension_test() {
+        ["sample_R1.fastq", "sample_R1.FQ.GZ", "sample_R1.fq.bz2", "sample_R1.fastq.zst"].iter()
+            .for_each(|f| assert!(SEQ_EXTENSIONS.is_match(f)));
+        ["contigs.fasta", "contigs.fa.gz", "sample_R1.fq.tar"].iter()
+            .for_each(|f| assert!(!SEQ_EXTENSIONS.is_match(f)));
+    }
+
+    #[test]
+    fn compression_test() {
+        assert_eq!(Compression::Bzip2, Compression::new(Path::new("sample_R1.fq.bz2")));
+        assert_eq!(Compression::Zstd, Compression::new(Path::new("sample_R1.fastq.zst")));
+        assert_eq!(Compression::Uncompressed, Compression::new(Path::new("sample_R1.fq")));
+        assert!(Compression::new(Path::new("sample_R1.fq.gz")).is_supported());
+        assert!(!Compression::new(Path::new("sample_R1.fq.bz2")).is_supported());
+    }
+
+    #[test]
+    fn glob_compressed_test() {
+        let seq = SeqReads::new("test_files/compressed_test");
+
+        let res = seq.glob_fastq().unwrap();
+        assert_eq!(2, res.len());
+    }
+
+    #[test]
+    fn library_key_test() {
+        let patterns = ReadPatterns::default();
+        let r1 = "sample_S1_L001_R1_001.fastq.gz";
+        let r2 = "sample_S1_L002_R2_001.fastq.gz";
+        let key = get_library_key(r1, &patterns.read_1);
+
+        assert_eq!("sample_S1*001.fastq", key);
+        assert_eq!(key, get_library_key(r2, &patterns.read_2));
+        assert_eq!("sample*", get_library_key("sample_1.fq.gz", &patterns.read_1));
+    }
+
+    #[test]
+    fn match_reads_file_name_test() {
+        let mut reads = SeqReads::new("PR1");
+        let files = vec![
+            PathBuf::from("PR1/MR1_001_R1.fq.gz"), 
+            PathBuf::from("PR1/MR1_001_R2.fq.gz"),
+            PathBuf::from("PR1/MR1_001_unpaired.fq.gz"),
+        ];
+        reads.match_reads(&files, &ReadPatterns::default()).unwrap();
+        let lib = &reads.libraries[0];
+
+        assert_eq!(vec![files[0].clone()], lib.read_1);
+        assert_eq!(vec![files[1].clone()], lib.read_2);
+        assert_eq!(vec![files[2].clone()], lib.singleton);
+    }
+
+    #[test]
+    fn mate_pair_names_test() {
+        let get_lib_types = |names: &[&str]| {
+            let mut reads = SeqReads::new("reads");
+            let files: Vec<PathBuf> = names.iter()
+                .map(|n| PathBuf::from("reads").join(n))
+                .collect();
+            reads.match_reads(&files, &ReadPatterns::default()).unwrap();
+            reads.libraries.iter().map(|l| l.lib_type).collect::<Vec<LibType>>()
+        };
+
+        assert_eq!(vec![LibType::PairedEnd], get_lib_types(&["MPH123_R1.fq.gz", "MPH123_R2.fq.gz"]));
+        assert_eq!(vec![LibType::PairedEnd], get_lib_types(&["Mateus_R1.fq.gz", "Mateus_R2.fq.gz"]));
+        assert_eq!(vec![LibType::PairedEnd], get_lib_types(&["MP_sample_R1.fq.gz", "MP_sample_R2.fq.gz"]));
+        assert_eq!(vec![LibType::MatePairs], get_lib_types(&["sample_MP_R1.fq.gz", "sample_MP_R2.fq.gz"]));
+        assert_eq!(vec![LibType::MatePairs], get_lib_types(&["sample_R1_mate.fq.gz", "sample_R2_mate.fq.gz"]));
+    }
+
+    #[test]
+    fn trimmomatic_reads_test() {
+        let mut reads = SeqReads::new("reads");
+        let files: Vec<PathBuf> = ["rat_R1_paired.fq.gz", "rat_R2_paired.fq.gz", 
+            "rat_R1_unpaired.fq.gz", "rat_R2_unpaired.fq.gz"].iter()
+            .map(|f| PathBuf::from("reads").join(f))
+            .collect();
+        reads.match_reads(&files, &ReadPatterns::default()).unwrap();
+        let lib = &reads.libraries[0];
+
+        assert_eq!(1, reads.libraries.len());
+        assert_eq!(vec![files[0].clone()], lib.read_1);
+        assert_eq!(vec![files[1].clone()], lib.read_2);
+        assert_eq!(vec![files[2].clone(), files[3].clone()], lib.singleton);
+    }
+
+    #[test]
+    fn match_numbered_reads_test() {
+        let mut reads = SeqReads::new("reads");
+        let files = vec![PathBuf::from("reads/rat_1.fq.gz"), PathBuf::from("reads/rat_2.fq.gz")];
+        reads.match_reads(&files, &ReadPatterns::default()).unwrap();
+
+        assert!(reads.is_single_pair());
+    }
+
+    #[test]
+    fn custom_patterns_test() {
+        let mut reads = SeqReads::new("reads");
+        let files = vec![PathBuf::from("reads/rat.fwd.fq.gz"), PathBuf::from("reads/rat.rev.fq.gz")];
+        let patterns = ReadPatterns::new(r"\.fwd\.", r"\.rev\.", "single").unwrap();
+        reads.match_reads(&files, &patterns).unwrap();
+
+        assert!(reads.is_single_pair());
+    }
+
+    #[test]
+    fn multiple_reads_error_test() {
+        let mut reads = SeqReads::new("reads");
+        let files = vec![
+            PathBuf::from("reads/rat_R1.fq.gz"), 
+            PathBuf::from("reads/rat_R1.fastq.gz"),
+            PathBuf::from("reads/rat_R2.fq.gz"),
+        ];
+        let err = reads.match_reads(&files, &ReadPatterns::default()).unwrap_err();
+
+        assert!(err.to_string().contains("MULTIPLE OR UNPAIRED R1/R2 CANDIDATES"));
+    }
+
+    #[test]
+    fn ambiguous_reads_error_test() {
+        let mut reads = SeqReads::new("reads");
+        let files = vec![PathBuf::from("reads/rat_R1_R2.fq.gz")];
+        let err = reads.match_reads(&files, &ReadPatterns::default()).unwrap_err();
+
+        assert!(err.to_string().contains("AMBIGUOUS READ FILES"));
+    }
+
+    #[test]
+    fn missing_reads_test() {
+        let mut sample = SeqDirs::new(2);
+        sample.id = String::from("empty");
+        sample.dir = String::from("test_files/spades_test");
+        let res = find_cleaned_fastq(&[sample], &ReadPatterns::default());
+
+        assert!(res.reads.is_empty());
+        assert!(matches!(res.failed[0].1, Error::MissingReads { .. }));
+    }
+
+    #[test]
+    fn listed_reads_test() {
+        let mut sample = SeqDirs::new(2);
+        sample.id = String::from("listed");
+        sample.dir = String::from("test_files");
+        sample.read_1 = Some(String::from("some_seq_ABC123_R1.fq.gz"));
+        sample.read_2 = Some(String::from("some_seq_ABC1234_R2.fq.gzip"));
+        let mut missing = SeqDirs::new(3);
+        missing.id = String::from("missing");
+        missing.read_1 = Some(String::from("test_files/missing_R1.fq.gz"));
+        let res = find_cleaned_fastq(&[sample, missing], &ReadPatterns::default());
+        let lib = &res.reads[0].libraries[0];
+
+        assert_eq!(LibType::PairedEnd, lib.lib_type);
+        assert_eq!(PathBuf::from("test_files/some_seq_ABC123_R1.fq.gz"), lib.read_1[0]);
+        assert_eq!("missing", res.failed[0].0);
+        assert!(matches!(res.failed[0].1, Error::InvalidReads { .. }));
+    }
+
+    fn get_duplicate_samples() -> Samples {
+        let mut samples = Samples::new();
+        ["reads/sample/run_1/trimmed", "reads/sample/run_2/trimmed", "reads/other/trimmed"].iter()
+            .for_each(|dir| {
+                let mut reads = SeqReads::new(dir);
+                reads.id = String::from("sample");
+                let mut lib = SeqLib::new(LibType::PairedEnd);
+                lib.read_1.push(Path::new(dir).join("R1.fq.gz"));
+                reads.libraries.push(lib);
+                samples.reads.push(reads);
+            });
+        samples.reads[2].id = String::from("other");
+
+        samples
+    }
+
+    #[test]
+    fn duplicate_ids_test() {
+        let mut error = get_duplicate_samples();
+        let mut suffix = get_duplicate_samples();
+        let mut parent = get_duplicate_samples();
+        let err = error.resolve_duplicates(DuplicateIds::Error).unwrap_err();
+        suffix.resolve_duplicates(DuplicateIds::Suffix).unwrap();
+        parent.resolve_duplicates(DuplicateIds::Parent).unwrap();
+        let ids = |s: &Samples| s.reads.iter().map(|r| r.id.clone()).collect::<Vec<String>>();
+
+        assert!(err.to_string().contains("sample: reads/sample/run_1/trimmed, reads/sample/run_2/trimmed"));
+        assert_eq!(vec!["sample", "sample_2", "other"], ids(&suffix));
+        assert_eq!(vec!["sample_run_1", "sample_run_2", "other"], ids(&parent));
+    }
+
+    #[test]
+    fn duplicate_files_test() {
+        let mut samples = get_duplicate_samples();
+        samples.reads[1].libraries[0].read_1 = samples.reads[0].libraries[0].read_1.clone();
+        samples.reads[1].id = String::from("another");
+        let err = samples.resolve_duplicates(DuplicateIds::Suffix).unwrap_err();
+
+        assert!(err.to_string().contains("reads/sample/run_1/trimmed/R1.fq.gz: sample, another"));
+    }
+
+    #[test]
+    fn read_warnings_test() {
+        let mut reads = SeqReads::new("test_files/trimmed_test");
+        reads.find_reads(&ReadPatterns::default()).unwrap();
+        let warnings = reads.get_warnings();
+
+        assert_eq!(2, warnings.len());
+        assert!(warnings[0].starts_with("EMPTY FILE"));
+    }
+
+    #[test]
+    fn multi_library_test() {
+        let dir = "test_files/multilib_test";
+        let mut res = Samples::new();
+        let mut sample = SeqDirs::new(2);
+        sample.id = String::from("sample");
+        sample.dir = String::from(dir);
+
+        get_cleaned_fastq(&sample, &mut res, &ReadPatterns::default());
+        let path = PathBuf::from(dir);
+        let reads = &res.reads[0];
+        let pe = &reads.libraries[0];
+        let mp = &reads.libraries[1];
+
+        assert_eq!(2, reads.libraries.len());
+        assert_eq!(LibType::PairedEnd, pe.lib_type);
+        assert_eq!(vec![path.join("sample_L001_R1.fq.gz"), path.join("sample_L002_R1.fq.gz")], 
+            pe.read_1);
+        assert_eq!(vec![path.join("sample_singleton.fq.gz")], pe.singleton);
+        assert_eq!(LibType::MatePairs, mp.lib_type);
+        assert_eq!(vec![path.join("sample_MP_R2.fq.gz")], mp.read_2);
+        assert!(!reads.is_single_pair());
+    }
+}
@@ -1,71 +1,150 @@
+use std::fs::File;
 use std::io::{self, Result, Write};
-use std::path::PathBuf;
+use std::path::Path;
 
-use crate::finder::{self, SeqReads};
-use crate::parser;
-use crate::runner;
+use crate::error::Error;
+use crate::finder::{self, AutoSearch, DuplicateIds, ReadPatterns, Samples};
+use crate::parser::{self, ConfigFormat, SeqDirs};
+use crate::report::{self, OutputFormat};
+use crate::runner::{self, RunnerOpts};
 use crate::utils;
 
 pub fn auto_process_input(
-    path: &str, 
-    dirname: &str, 
-    threads: &Option<usize>, 
-    outdir: &Option<PathBuf>,
-    args: &Option<String>
-) {
-    let samples = finder::auto_find_cleaned_fastq(path, dirname);
-    runner::assemble_reads(&samples, threads, outdir, args);
+    search: &AutoSearch, 
+    patterns: &ReadPatterns, 
+    duplicates: DuplicateIds,
+    opts: &RunnerOpts
+) -> std::result::Result<(), Error> {
+    let mut samples = finder::auto_find_cleaned_fastq(search, patterns);
+    samples.resolve_duplicates(duplicates)?;
+    runner::assemble_reads(&samples, opts)
 }
 
 pub fn process_input(
     input: &str, 
-    threads: &Option<usize>, 
-    outdir: &Option<PathBuf>,
-    args: &Option<String>
-) {
-    let dirs = parser::parse_seqdir(input);
-    let samples = finder::find_cleaned_fastq(&dirs);
-    runner::assemble_reads(&samples, threads, outdir, args);
+    patterns: &ReadPatterns, 
+    duplicates: DuplicateIds,
+    opts: &RunnerOpts
+) -> std::result::Result<(), Error> {
+    let dirs = parser::parse_seqdir(input)?;
+    let mut samples = finder::find_cleaned_fastq(&dirs, patterns);
+    samples.resolve_duplicates(duplicates)?;
+    runner::assemble_reads(&samples, opts)
 }
 
-pub fn auto_dryrun(path: &str, dirname: &str) {
-    let samples = finder::auto_find_cleaned_fastq(path, dirname);
-    utils::get_system_info().unwrap();
-    print_dryrun(& samples).unwrap();
+pub fn auto_dryrun(
+    search: &AutoSearch, 
+    patterns: &ReadPatterns,
+    duplicates: DuplicateIds,
+    format: OutputFormat
+) -> std::result::Result<(), Error> {
+    let mut samples = finder::auto_find_cleaned_fastq(search, patterns);
+    samples.resolve_duplicates(duplicates)?;
+    write_dryrun(&samples, format)?;
+    check_failed_samples(&samples)
 }
 
-pub fn dryrun(input: &str) {
-    let dirs = parser::parse_seqdir(input);
-    let samples = finder::find_cleaned_fastq(&dirs);
-    utils::get_system_info().unwrap();
-    print_dryrun(&samples).unwrap();
+pub fn dryrun(
+    input: &str, 
+    patterns: &ReadPatterns, 
+    duplicates: DuplicateIds,
+    format: OutputFormat
+) -> std::result::Result<(), Error> {
+    let dirs = parser::parse_seqdir(input)?;
+    let mut samples = finder::find_cleaned_fastq(&dirs, patterns);
+    samples.resolve_duplicates(duplicates)?;
+    write_dryrun(&samples, format)?;
+    check_failed_samples(&samples)
+}
+
+// Writes the samples found by auto search to a config file.
+// Users can edit the IDs or remove samples before running assembly.
+pub fn init_config(
+    search: &AutoSearch, 
+    patterns: &ReadPatterns,
+    duplicates: DuplicateIds,
+    output: &Path,
+    format: ConfigFormat
+) -> std::result::Result<(), Error> {
+    if output.exists() {
+        return Err(Error::InvalidInput(format!("{:?} EXISTS. PLEASE RENAME OR REMOVE IT", output)));
+    }
+
+    let mut samples = finder::auto_find_cleaned_fastq(search, patterns);
+    samples.resolve_duplicates(duplicates)?;
+    samples.reads.sort_by(|a, b| a.dir.cmp(&b.dir));
+    let dirs: Vec<SeqDirs> = samples.reads.iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let mut sample = SeqDirs::new(i + 2);
+            sample.id = r.id.clone();
+            sample.dir = r.dir.to_string_lossy().to_string();
+            sample
+        })
+        .collect();
+
+    let mut file = io::BufWriter::new(File::create(output)?);
+    parser::write_seqdir(&mut file, &dirs, format)?;
+    file.flush()?;
+
+    let out = io::stdout();
+    let mut handle = io::BufWriter::new(out);
+    writeln!(handle, "\x1b[0;33mTotal samples: {}\x1b[0m", dirs.len())?;
+    writeln!(handle, "Config\t\t: {}\n", output.to_string_lossy())?;
+    utils::print_failed_samples(&mut handle, &samples.failed)?;
+    handle.flush()?;
+
+    check_failed_samples(&samples)
 }
 
-pub fn check_dependencies() {
-    utils::get_system_info().unwrap();
+pub fn check_dependencies() -> std::result::Result<(), Error> {
+    utils::get_system_info()?;
     println!("\x1b[0;33mDependencies:\x1b[0m");
     runner::check_spades();
     println!();
+
+    Ok(())
+}
+
+fn write_dryrun(samples: &Samples, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Text => {
+            utils::get_system_info()?;
+            print_dryrun(samples)
+        }
+        _ => report::write_dryrun(samples, format),
+    }
 }
 
-fn print_dryrun(dirs: &[SeqReads]) -> Result<()> {
+fn print_dryrun(samples: &Samples) -> Result<()> {
     let out = io::stdout();
     let mut handle = io::BufWriter::new(out);
 
-    writeln!(handle,"\x1b[0;33mTotal samples: {}\n\x1b[0m", dirs.len())?;
-    dirs.iter()
-        .for_each(|e| {
-            writeln!(handle,"\x1b[0;32mID\t\t: {}\x1b[0m", e.id).unwrap();
-            writeln!(handle,"Dir\t\t: {}", e.dir.to_string_lossy()).unwrap();
-            writeln!(handle,"Read 1\t\t: {}", e.read_1.to_string_lossy()).unwrap();
-            writeln!(handle,"Read 2\t\t: {}", e.read_2.to_string_lossy()).unwrap();
+    writeln!(handle,"\x1b[0;33mTotal samples: {}\n\x1b[0m", samples.reads.len())?;
+    samples.reads.iter()
+        .try_for_each(|e| {
+            writeln!(handle,"\x1b[0;32mID\t\t: {}\x1b[0m", e.id)?;
+            writeln!(handle,"Dir\t\t: {}", e.dir.to_string_lossy())?;
+            e.write_reads(&mut handle)?;
+            e.params.write_params(&mut handle)?;
+            e.get_warnings().iter()
+                .try_for_each(|w| writeln!(handle, "\x1b[0;33mWarning\t\t: {}\x1b[0m", w))?;
 
-            if e.singleton.is_some() {
-                writeln!(handle,"Singleton\t: {}", e.singleton.as_ref().unwrap().to_string_lossy()).unwrap();
-            }
-
-            writeln!(handle).unwrap();
-        });
+            writeln!(handle)
+        })?;
+    utils::print_failed_samples(&mut handle, &samples.failed)?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+fn check_failed_samples(samples: &Samples) -> std::result::Result<(), Error> {
+    if samples.failed.is_empty() {
+        return Ok(());
+    }
+
+    let ids = samples.failed.iter()
+        .map(|(id, _)| id.clone())
+        .collect();
+
+    Err(Error::FailedSamples(ids))
+}
@@ -0,0 +1,59 @@
+// Batch-level log with timestamps.
+// Concurrent jobs share the same log, so we guard the file with a mutex.
+use std::fs::{File, OpenOptions};
+use std::io::{Result, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use chrono::Local;
+
+pub const LOG_FILE: &str = "spades-runner.log";
+
+pub struct Logger {
+    file: Mutex<File>,
+}
+
+impl Logger {
+    // Appends to the existing log, so resumed batches keep the history.
+    pub fn new(dir: &Path) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(dir.join(LOG_FILE))?;
+
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn log(&self, msg: &str) -> Result<()> {
+        let mut file = self.file.lock().expect("LOG FILE LOCK IS POISONED");
+        write_log(&mut *file, msg)
+    }
+}
+
+pub fn write_log<W: Write>(handle: &mut W, msg: &str) -> Result<()> {
+    writeln!(handle, "[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), msg)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn logger_test() {
+        let dir = std::env::temp_dir().join("spr_logger_test");
+        fs::create_dir_all(&dir).unwrap();
+        let logger = Logger::new(&dir).unwrap();
+        logger.log("Starting batch").unwrap();
+        logger.log("Batch finished").unwrap();
+
+        let log = fs::read_to_string(dir.join(LOG_FILE)).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(2, log.lines().count());
+        assert!(log.starts_with('['));
+        assert!(log.contains("] Batch finished"));
+    }
+}
@@ -2,15 +2,32 @@
 // March 2021
 // MIT
 
+// Newer clippy versions flag the original code for these.
+#![allow(clippy::needless_borrow)]
+#![allow(clippy::manual_unwrap_or_default)]
+#![allow(clippy::manual_is_multiple_of)]
+
 mod cleaner;
 mod cli;
+mod dataset;
+mod decompressor;
+mod error;
 mod finder;
 mod io;
+mod logger;
+mod params;
 mod parser;
+mod progress;
+mod report;
 mod runner;
+mod stats;
+mod summary;
+mod usage;
 mod utils;
+mod validator;
 
 
+use std::process;
 use std::time::Instant;
 
 use clap::crate_version;
@@ -18,15 +35,24 @@ use clap::crate_version;
 fn main() {
     let version = crate_version!();
     let time = Instant::now();
-    cli::get_cli(&version);
+    let args = cli::get_cli(&version);
+    let res = cli::run(&args, version);
     let duration = time.elapsed();
 
-    if duration.as_secs() < 60 {
-        println!("Execution time: {:?}", duration);
-    } else {
-        utils::print_formatted_duration(duration.as_secs());
+    // Keeps the structured output clean for other programs.
+    if !cli::is_structured_output(&args) {
+        if duration.as_secs() < 60 {
+            println!("Execution time: {:?}", duration);
+        } else {
+            utils::print_formatted_duration(duration.as_secs());
+        }
+
+        println!("Thank you for using spades-runner v{} 😊", &version);
     }
 
-    println!("Thank you for using spades-runner v{} 😊", &version);
+    if let Err(e) = res {
+        eprintln!("\x1b[41m[ERROR]\x1b[0m {}", e);
+        process::exit(1);
+    }
 }
 
@@ -0,0 +1,398 @@
+// Parse and validate optional SPAdes parameters.
+// The runner owns the input, output, and threading flags.
+// Hence, users cannot set them through --opts.
+use std::io::{Result as IoResult, Write};
+
+use crate::error::Error;
+
+// SPAdes flags accepted in --opts and whether they take a value.
+const SPADES_FLAGS: [(&str, bool); 33] = [
+    ("--isolate", false),
+    ("--sc", false),
+    ("--meta", false),
+    ("--bio", false),
+    ("--corona", false),
+    ("--rna", false),
+    ("--plasmid", false),
+    ("--metaviral", false),
+    ("--metaplasmid", false),
+    ("--rnaviral", false),
+    ("--iontorrent", false),
+    ("--only-error-correction", false),
+    ("--only-assembler", false),
+    ("--careful", false),
+    ("--disable-gzip-output", false),
+    ("--disable-rr", false),
+    ("--gfa11", false),
+    ("--checkpoints", true),
+    ("--cov-cutoff", true),
+    ("--phred-offset", true),
+    ("--custom-hmms", true),
+    ("--read-cov-threshold", true),
+    ("--tmp-dir", true),
+    ("-m", true),
+    ("--memory", true),
+    ("-k", true),
+    ("--trusted-contigs", true),
+    ("--untrusted-contigs", true),
+    ("--sanger", true),
+    ("--pacbio", true),
+    ("--nanopore", true),
+    ("--tslr", true),
+    ("--assembly-graph", true),
+];
+
+// Flags set by spades-runner itself.
+const RUNNER_FLAGS: [&str; 19] = [
+    "-o",
+    "--pe1-1",
+    "--pe1-2",
+    "--pe1-s",
+    "--pe1-12",
+    "-1",
+    "-2",
+    "-s",
+    "--12",
+    "--merged",
+    "--dataset",
+    "-t",
+    "--threads",
+    "--continue",
+    "--restart-from",
+    "-h",
+    "--help",
+    "-v",
+    "--version",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Default,
+    Isolate,
+    Sc,
+    Meta,
+    Rna,
+    Plasmid,
+    Bio,
+    Corona,
+}
+
+impl Mode {
+    pub fn new(mode: &str) -> Result<Self, Error> {
+        Self::from_name(mode)
+            .ok_or_else(|| Error::InvalidInput(format!("UNKNOWN SPAdes MODE: {}", mode)))
+    }
+
+    pub fn from_name(mode: &str) -> Option<Self> {
+        match mode {
+            "default" => Some(Mode::Default),
+            "isolate" => Some(Mode::Isolate),
+            "sc" => Some(Mode::Sc),
+            "meta" => Some(Mode::Meta),
+            "rna" => Some(Mode::Rna),
+            "plasmid" => Some(Mode::Plasmid),
+            "bio" => Some(Mode::Bio),
+            "corona" => Some(Mode::Corona),
+            _ => None,
+        }
+    }
+
+    fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "--isolate" => Some(Mode::Isolate),
+            "--sc" => Some(Mode::Sc),
+            "--meta" => Some(Mode::Meta),
+            "--rna" => Some(Mode::Rna),
+            "--plasmid" => Some(Mode::Plasmid),
+            "--bio" => Some(Mode::Bio),
+            "--corona" => Some(Mode::Corona),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Mode::Default => "default",
+            Mode::Isolate => "isolate",
+            Mode::Sc => "sc",
+            Mode::Meta => "meta",
+            Mode::Rna => "rna",
+            Mode::Plasmid => "plasmid",
+            Mode::Bio => "bio",
+            Mode::Corona => "corona",
+        }
+    }
+
+    pub fn executable(&self) -> &str {
+        match self {
+            Mode::Meta => "metaspades.py",
+            Mode::Rna => "rnaspades.py",
+            Mode::Corona => "coronaspades.py",
+            _ => "spades.py",
+        }
+    }
+
+    pub fn flag(&self) -> Option<&str> {
+        match self {
+            Mode::Isolate => Some("--isolate"),
+            Mode::Sc => Some("--sc"),
+            Mode::Plasmid => Some("--plasmid"),
+            Mode::Bio => Some("--bio"),
+            _ => None,
+        }
+    }
+
+    // SPAdes only supports --careful in the multi-cell,
+    // single-cell, and plasmid pipelines.
+    pub fn allows_careful(&self) -> bool {
+        matches!(self, Mode::Default | Mode::Sc | Mode::Plasmid)
+    }
+
+    pub fn requires_paired_reads(&self) -> bool {
+        *self == Mode::Meta
+    }
+}
+
+// Per-sample settings from the sample sheet.
+// They override --opts, --mode, and --threads for the sample.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SampleParams {
+    pub args: Option<Vec<String>>,
+    pub mode: Option<Mode>,
+    pub threads: Option<usize>,
+    // In Gb, passed to SPAdes -m.
+    pub memory: Option<usize>,
+}
+
+impl SampleParams {
+    pub fn write_params<W: Write>(&self, handle: &mut W) -> IoResult<()> {
+        if let Some(threads) = self.threads {
+            writeln!(handle, "Threads\t\t: {}", threads)?;
+        }
+
+        if let Some(memory) = self.memory {
+            writeln!(handle, "Memory\t\t: {} Gb", memory)?;
+        }
+
+        if let Some(mode) = self.mode {
+            writeln!(handle, "Mode\t\t: {}", mode.as_str())?;
+        }
+
+        if let Some(args) = &self.args {
+            writeln!(handle, "Opt params\t: {}", shell_words::join(args))?;
+        }
+
+        Ok(())
+    }
+}
+
+pub fn has_memory_params(tokens: &[String]) -> bool {
+    tokens.iter()
+        .any(|t| matches!(split_flag(t).0, "-m" | "--memory"))
+}
+
+// Drops the memory limit, so a per-sample limit can replace it.
+pub fn remove_memory_params(tokens: &[String]) -> Vec<String> {
+    let mut params = Vec::new();
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        match split_flag(token) {
+            ("-m", None) | ("--memory", None) => {
+                tokens.next();
+            }
+            ("--memory", Some(_)) => (),
+            _ => params.push(token.clone()),
+        }
+    }
+
+    params
+}
+
+pub fn parse_params(input: &str, mode: &Mode) -> Result<Vec<String>, Error> {
+    let input = input.trim();
+    let input = input.strip_prefix("params=").unwrap_or(input);
+    let tokens = shell_words::split(input)
+        .map_err(|e| invalid(format!("INVALID OPTIONAL PARAMS {}: {}", input, e)))?;
+    check_params(&tokens)?;
+    check_mode_params(&tokens, mode)?;
+
+    Ok(tokens)
+}
+
+fn check_params(tokens: &[String]) -> Result<(), Error> {
+    let mut tokens = tokens.iter();
+    while let Some(token) = tokens.next() {
+        let (flag, value) = split_flag(token);
+        if RUNNER_FLAGS.contains(&flag) {
+            return Err(invalid(format!("{} IS SET BY spades-runner. \
+                PLEASE REMOVE IT FROM THE OPTIONAL PARAMS.", flag)));
+        }
+
+        match SPADES_FLAGS.iter().find(|(f, _)| *f == flag) {
+            Some((_, true)) if value.is_none() => match tokens.next() {
+                Some(next) if is_known_flag(next) => 
+                    return Err(invalid(format!("MISSING VALUE FOR {}. FOUND {}", flag, next))),
+                Some(_) => (),
+                None => return Err(invalid(format!("MISSING VALUE FOR {}", flag))),
+            },
+            Some((_, true)) => (),
+            Some((_, false)) => {
+                if value.is_some() {
+                    return Err(invalid(format!("{} DOES NOT TAKE A VALUE", flag)));
+                }
+            }
+            None => return Err(invalid(format!("UNKNOWN SPAdes PARAMETER: {}", token))),
+        }
+    }
+
+    Ok(())
+}
+
+fn is_known_flag(token: &str) -> bool {
+    let flag = split_flag(token).0;
+    RUNNER_FLAGS.contains(&flag) || SPADES_FLAGS.iter().any(|(f, _)| *f == flag)
+}
+
+// Pipeline flags should be set using --mode.
+// We still accept them in the params if the mode is default.
+pub fn check_mode_params(tokens: &[String], mode: &Mode) -> Result<(), Error> {
+    let mode_flags: Vec<Mode> = tokens.iter()
+        .filter_map(|t| Mode::from_flag(t))
+        .collect();
+
+    if *mode != Mode::Default && !mode_flags.is_empty() {
+        return Err(invalid(format!("PIPELINE FLAGS IN THE OPTIONAL PARAMS CONFLICT WITH --mode {}. \
+            PLEASE USE --mode ONLY.", mode.as_str())));
+    }
+
+    if mode_flags.len() > 1 {
+        return Err(invalid(format!("FOUND MULTIPLE PIPELINE FLAGS IN THE OPTIONAL PARAMS: {:?}", 
+            mode_flags)));
+    }
+
+    let mode = get_effective_mode(tokens, mode);
+    if !mode.allows_careful() && tokens.iter().any(|t| t == "--careful") {
+        return Err(invalid(format!("--careful IS NOT COMPATIBLE WITH {} MODE", mode.as_str())));
+    }
+
+    Ok(())
+}
+
+// A pipeline flag in the params sets the mode if the mode is default.
+pub fn get_effective_mode(tokens: &[String], mode: &Mode) -> Mode {
+    if *mode != Mode::Default {
+        return *mode;
+    }
+
+    tokens.iter()
+        .find_map(|t| Mode::from_flag(t))
+        .unwrap_or(*mode)
+}
+
+fn invalid(msg: String) -> Error {
+    Error::InvalidInput(msg)
+}
+
+// Handles the --flag=value form.
+fn split_flag(token: &str) -> (&str, Option<&str>) {
+    match token.split_once('=') {
+        Some((flag, value)) if token.starts_with("--") => (flag, Some(value)),
+        _ => (token, None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_params_test() {
+        let input = "params=--careful -m 16";
+        let res = parse_params(input, &Mode::Default).unwrap();
+
+        assert_eq!(vec!["--careful", "-m", "16"], res);
+    }
+
+    #[test]
+    fn parse_quoted_params_test() {
+        let input = "--careful --tmp-dir '/scratch/spades tmp' -k 21,33,55";
+        let res = parse_params(input, &Mode::Default).unwrap();
+
+        assert_eq!(vec!["--careful", "--tmp-dir", "/scratch/spades tmp", "-k", "21,33,55"], res);
+    }
+
+    #[test]
+    fn parse_equal_params_test() {
+        let input = "--cov-cutoff=auto";
+        let res = parse_params(input, &Mode::Default).unwrap();
+
+        assert_eq!(vec!["--cov-cutoff=auto"], res);
+    }
+
+    #[test]
+    fn runner_params_error_test() {
+        let err = parse_params("--careful -o some_dir", &Mode::Default).unwrap_err();
+        assert!(err.to_string().contains("IS SET BY spades-runner"));
+    }
+
+    #[test]
+    fn unknown_params_error_test() {
+        let err = parse_params("--carefull", &Mode::Default).unwrap_err();
+        assert!(err.to_string().contains("UNKNOWN SPAdes PARAMETER"));
+    }
+
+    #[test]
+    fn missing_value_error_test() {
+        let err = parse_params("--careful -m", &Mode::Default).unwrap_err();
+        assert!(err.to_string().contains("MISSING VALUE"));
+    }
+
+    #[test]
+    fn flag_as_value_error_test() {
+        let err = parse_params("-k --careful", &Mode::Default).unwrap_err();
+        assert!(err.to_string().contains("MISSING VALUE FOR -k. FOUND --careful"));
+
+        let err = parse_params("-m --threads=8", &Mode::Default).unwrap_err();
+        assert!(err.to_string().contains("MISSING VALUE FOR -m. FOUND --threads=8"));
+        assert!(parse_params("-k 21,33 --careful", &Mode::Default).is_ok());
+    }
+
+    #[test]
+    fn mode_test() {
+        let mode = Mode::new("meta").unwrap();
+
+        assert_eq!("metaspades.py", mode.executable());
+        assert_eq!(None, mode.flag());
+        assert!(!mode.allows_careful());
+        assert_eq!(Some("--isolate"), Mode::new("isolate").unwrap().flag());
+        assert!(Mode::new("metagenome").is_err());
+    }
+
+    #[test]
+    fn careful_meta_error_test() {
+        let err = parse_params("--careful -m 16", &Mode::Meta).unwrap_err();
+        assert!(err.to_string().contains("NOT COMPATIBLE WITH meta MODE"));
+    }
+
+    #[test]
+    fn careful_isolate_flag_error_test() {
+        let err = parse_params("--isolate --careful", &Mode::Default).unwrap_err();
+        assert!(err.to_string().contains("NOT COMPATIBLE WITH isolate MODE"));
+    }
+
+    #[test]
+    fn mode_conflict_error_test() {
+        let err = parse_params("--rna", &Mode::Meta).unwrap_err();
+        assert!(err.to_string().contains("CONFLICT WITH --mode"));
+    }
+
+    #[test]
+    fn remove_memory_params_test() {
+        let tokens: Vec<String> = ["--careful", "-m", "16", "--memory=32", "-k", "21"].iter()
+            .map(|t| t.to_string())
+            .collect();
+
+        assert!(has_memory_params(&tokens));
+        assert_eq!(vec!["--careful", "-k", "21"], remove_memory_params(&tokens));
+    }
+}
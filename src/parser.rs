@@ -1,61 +1,276 @@
-use std::fs::File;
-use std::io::BufReader;
-use std::io::prelude::*;
+use std::fs;
+use std::io::{self, Write};
 
+use crate::error::Error;
+use crate::params::{self, Mode, SampleParams};
 use crate::utils;
 
-pub fn parse_seqdir(input: &str) -> Vec<SeqDirs> {
-    let file = File::open(input).unwrap();
-    let buff = BufReader::new(file);
+// Columns of the sample sheet. Only id is required,
+// and either dir or r1 should be present.
+const COLUMNS: [&str; 9] = [
+    "id", "dir", "r1", "r2", "singleton", "opts", "mode", "threads", "memory"
+];
+
+// The samples and the errors of the invalid lines.
+type ParseResult = Result<(Vec<SeqDirs>, Vec<Error>), Error>;
+
+// Accepts a sample sheet with a named header, e.g. id,dir,r1,r2,
+// or the two-column csv and ini config.
+pub fn parse_seqdir(input: &str) -> Result<Vec<SeqDirs>, Error> {
+    get_valid_samples(parse_seqdir_lines(input)?)
+}
+
+// Keeps parsing after an invalid line to report every problem at once.
+pub fn parse_seqdir_lines(input: &str) -> ParseResult {
+    let (text, mut errors) = read_config(input)?;
+    let (samples, line_errors) = parse_text(&text)?;
+    errors.extend(line_errors);
+
+    Ok((samples, errors))
+}
+
+// Unreadable lines, e.g. invalid UTF-8, are parsed as blank lines
+// to keep the line numbers of the other lines.
+// Each of them is reported as a config error.
+fn read_config(input: &str) -> Result<(String, Vec<Error>), Error> {
+    let bytes = fs::read(input)?;
+    let mut errors = Vec::new();
+    let lines: Vec<&str> = bytes.split(|b| *b == b'\n')
+        .enumerate()
+        .map(|(i, line)| match std::str::from_utf8(line) {
+            Ok(line) => line,
+            Err(e) => {
+                errors.push(Error::Config {
+                    line: i + 1,
+                    msg: format!("UNREADABLE LINE: {}", e)
+                });
+                ""
+            }
+        })
+        .collect();
+
+    Ok((lines.join("\n"), errors))
+}
+
+fn parse_text(text: &str) -> ParseResult {
+    if is_sample_sheet(text) {
+        parse_sample_sheet(text)
+    } else {
+        Ok(parse_config(text))
+    }
+}
+
+fn get_valid_samples((samples, errors): (Vec<SeqDirs>, Vec<Error>)) -> Result<Vec<SeqDirs>, Error> {
+    match errors.into_iter().next() {
+        Some(e) => Err(e),
+        None => Ok(samples),
+    }
+}
+
+fn is_sample_sheet(text: &str) -> bool {
+    match text.lines().find(|l| !is_skipped_line(l)) {
+        Some(header) => {
+            let columns = utils::split_strings(&header.to_lowercase(), ',');
+            columns.iter().any(|c| c == "id")
+                && columns.iter().any(|c| c == "dir" || c == "r1")
+        }
+        None => false,
+    }
+}
+
+fn is_skipped_line(line: &str) -> bool {
+    let line = line.trim();
+    line.is_empty() || line.starts_with('#')
+}
+
+// The two-column config. The first line is the header.
+fn parse_config(text: &str) -> (Vec<SeqDirs>, Vec<Error>) {
+    let header = text.lines()
+        .position(|l| !is_skipped_line(l))
+        .unwrap_or(0);
+    let mut seqdir = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate().skip(header + 1) {
+        if is_skipped_line(line) {
+            continue;
+        }
+
+        let mut sample = SeqDirs::new(i + 1);
+        let res = if line.contains(',') {
+            sample.parse_csv(line)
+        } else if line.contains(':') {
+            sample.parse_ini(line)
+        } else {
+            Err(Error::Config {
+                line: sample.line,
+                msg: format!("LOOKING FOR ',' or ':' FOUND {}", line)
+            })
+        };
+
+        match res {
+            Ok(()) => seqdir.push(sample),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    (seqdir, errors)
+}
+
+// Only the header errors stop the parsing.
+fn parse_sample_sheet(text: &str) -> ParseResult {
+    let mut reader = csv::ReaderBuilder::new()
+        .comment(Some(b'#'))
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let header_line = text.lines()
+        .position(|l| !is_skipped_line(l))
+        .unwrap_or(0) + 1;
+    let headers = reader.headers()
+        .map_err(|e| get_csv_error(text, e, header_line))?;
+    let columns = get_columns(headers, header_line)?;
 
     let mut seqdir = Vec::new();
-    buff.lines()
-        .filter_map(|ok| ok.ok())
-        .skip(1)
-        .for_each(|line| {
-            let mut sample = SeqDirs::new();
-
-            if line.contains(',') {
-                sample.parse_csv(&line);
-            } else if line.contains(':') {
-                sample.parse_ini(&line);
-            } else {
-                panic!("INVALID INPUT FORMAT. \
-                    LOOKING FOR ',' or ':' FOUND {}", line);
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                errors.push(get_csv_error(text, e, header_line));
+                continue;
             }
-            
-            seqdir.push(sample);
-        });
+        };
+        let line = record.position()
+            .map(|p| get_line_number(text, p))
+            .unwrap_or(header_line);
+        let mut sample = SeqDirs::new(line);
+        match sample.parse_record(&columns, &record) {
+            Ok(()) => seqdir.push(sample),
+            Err(e) => errors.push(e),
+        }
+    }
 
-    seqdir
+    Ok((seqdir, errors))
 }
 
+fn get_columns(headers: &csv::StringRecord, line: usize) -> Result<Vec<String>, Error> {
+    let columns: Vec<String> = headers.iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+
+    for (i, col) in columns.iter().enumerate() {
+        if !COLUMNS.contains(&col.as_str()) {
+            return Err(Error::Config {
+                line,
+                msg: format!("UNKNOWN COLUMN {}. EXPECTING {}", col, COLUMNS.join(", "))
+            });
+        }
+
+        if columns[..i].contains(col) {
+            return Err(Error::Config { line, msg: format!("DUPLICATE COLUMN {}", col) });
+        }
+    }
+
+    Ok(columns)
+}
+
+fn get_csv_error(text: &str, err: csv::Error, header_line: usize) -> Error {
+    let line = err.position()
+        .map(|p| get_line_number(text, p))
+        .unwrap_or(header_line);
+
+    Error::Config { line, msg: err.to_string() }
+}
+
+// The csv reader does not count the comment lines,
+// and the record position starts before the skipped lines.
+// Hence, we count the lines up to the record ourselves.
+fn get_line_number(text: &str, pos: &csv::Position) -> usize {
+    let end = (pos.byte() as usize).min(text.len());
+    let start = text.as_bytes()[..end].iter()
+        .filter(|&&b| b == b'\n')
+        .count();
+    let skipped = text.lines()
+        .skip(start)
+        .take_while(|l| is_skipped_line(l))
+        .count();
+
+    start + skipped + 1
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConfigFormat {
+    Csv,
+    Ini,
+}
+
+impl ConfigFormat {
+    pub fn new(format: &str) -> Result<Self, Error> {
+        match format {
+            "csv" => Ok(ConfigFormat::Csv),
+            "ini" => Ok(ConfigFormat::Ini),
+            _ => Err(Error::InvalidInput(format!("UNSUPPORTED CONFIG FORMAT: {}", format))),
+        }
+    }
+}
+
+// Writes a config that parse_seqdir() can read back.
+pub fn write_seqdir<W: Write>(
+    handle: &mut W,
+    samples: &[SeqDirs],
+    format: ConfigFormat
+) -> io::Result<()> {
+    match format {
+        ConfigFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(handle);
+            writer.write_record(["id", "dir"])?;
+            samples.iter()
+                .try_for_each(|s| writer.write_record([&s.id, &s.dir]))?;
+            writer.flush()
+        }
+        ConfigFormat::Ini => {
+            writeln!(handle, "[samples]")?;
+            samples.iter()
+                .try_for_each(|s| writeln!(handle, "{}:{}", s.id, s.dir))
+        }
+    }
+}
 
+#[derive(Debug, Default)]
 pub struct SeqDirs {
     pub id: String,
     pub dir: String,
+    pub line: usize,
+    pub read_1: Option<String>,
+    pub read_2: Option<String>,
+    pub singleton: Option<String>,
+    pub params: SampleParams,
 }
 
 impl SeqDirs {
-    fn new() -> Self {
+    pub fn new(line: usize) -> Self {
         Self {
-            id: String::new(),
-            dir: String::new(),
+            line,
+            ..Default::default()
         }
     }
 
-    fn parse_csv(&mut self, line: &str) {
+    fn parse_csv(&mut self, line: &str) -> Result<(), Error> {
         let sep = ',';
         let lines = utils::split_strings(line, sep);
-        self.check_results(&lines);
+        self.check_results(&lines)?;
         self.parse_samples(&lines);
+
+        Ok(())
     }
 
-    fn parse_ini(&mut self, line: &str) {
+    fn parse_ini(&mut self, line: &str) -> Result<(), Error> {
         let sep = ':';
         let lines = utils::split_strings(line, sep);
-        self.check_results(&lines);
+        self.check_results(&lines)?;
         self.parse_samples(&lines);
+
+        Ok(())
     }
 
     fn parse_samples(&mut self, lines: &[String]) {
@@ -63,11 +278,99 @@ impl SeqDirs {
         self.dir = String::from(&lines[1]);
     }
 
-    fn check_results(&self, lines: &[String]) {
+    fn check_results(&self, lines: &[String]) -> Result<(), Error> {
         if lines.len() != 2 {
-            panic!("INVALID INPUT. EXPECTING ID AND DIRECTORY PATH, \
-                FOUND: {:?}", lines)
+            return Err(Error::Config {
+                line: self.line,
+                msg: format!("EXPECTING ID AND DIRECTORY PATH, FOUND: {:?}", lines)
+            });
         }
+
+        Ok(())
+    }
+
+    // Missing and empty fields are None.
+    fn parse_record(&mut self, columns: &[String], record: &csv::StringRecord) -> Result<(), Error> {
+        if record.len() > columns.len() {
+            return Err(Error::Config {
+                line: self.line,
+                msg: format!("EXPECTING {} COLUMNS, FOUND {}", columns.len(), record.len())
+            });
+        }
+
+        let mut opts = None;
+        for (col, value) in columns.iter().zip(record.iter()) {
+            if value.is_empty() {
+                continue;
+            }
+
+            let value = value.to_string();
+            match col.as_str() {
+                "id" => self.id = value,
+                "dir" => self.dir = value,
+                "r1" => self.read_1 = Some(value),
+                "r2" => self.read_2 = Some(value),
+                "singleton" => self.singleton = Some(value),
+                "opts" => opts = Some(value),
+                "mode" => self.params.mode = Some(self.parse_mode(&value)?),
+                "threads" => self.params.threads = Some(self.parse_number(&value, "THREAD NUMBERS")?),
+                "memory" => self.params.memory = Some(self.parse_number(&value, "MEMORY LIMIT")?),
+                _ => (),
+            }
+        }
+
+        if let Some(opts) = opts {
+            self.parse_opts(&opts)?;
+        }
+
+        self.check_record()
+    }
+
+    fn parse_mode(&self, value: &str) -> Result<Mode, Error> {
+        Mode::from_name(value)
+            .ok_or_else(|| self.invalid(format!("UNKNOWN SPAdes MODE: {}", value)))
+    }
+
+    fn parse_number(&self, value: &str, name: &str) -> Result<usize, Error> {
+        match value.parse::<usize>() {
+            Ok(num) if num > 0 => Ok(num),
+            _ => Err(self.invalid(format!("INVALID {}: {}", name, value))),
+        }
+    }
+
+    // Checked against the sample mode here.
+    // The runner checks it again against --mode if the sample has no mode.
+    fn parse_opts(&mut self, value: &str) -> Result<(), Error> {
+        let mode = self.params.mode.unwrap_or(Mode::Default);
+        let args = params::parse_params(value, &mode)
+            .map_err(|e| self.invalid(e.to_string()))?;
+
+        if self.params.memory.is_some() && params::has_memory_params(&args) {
+            return Err(self.invalid(String::from("SET THE MEMORY LIMIT \
+                USING EITHER THE memory COLUMN OR -m IN opts")));
+        }
+
+        self.params.args = Some(args);
+
+        Ok(())
+    }
+
+    fn invalid(&self, msg: String) -> Error {
+        Error::Config { line: self.line, msg }
+    }
+
+    fn check_record(&self) -> Result<(), Error> {
+        let msg = if self.id.is_empty() {
+            "MISSING SAMPLE ID"
+        } else if self.dir.is_empty() && self.read_1.is_none() {
+            "EXPECTING A DIRECTORY OR READ 1 PATH"
+        } else if self.read_1.is_none() && (self.read_2.is_some() || self.singleton.is_some()) {
+            "READ 2 AND SINGLETON PATHS NEED A READ 1 PATH"
+        } else {
+            return Ok(());
+        };
+
+        Err(Error::Config { line: self.line, msg: msg.to_string() })
     }
 }
 
@@ -78,7 +381,7 @@ mod test {
     #[test]
     fn input_ini_test() {
         let input = "test_files/spade_runner.ini";
-        let samples = parse_seqdir(&input);
+        let samples = parse_seqdir(input).unwrap();
 
         assert_eq!(2, samples.len());
     }
@@ -86,9 +389,9 @@ mod test {
     #[test]
     fn split_csv_test() {
         let line = "some_animals,folder/target/";
-        let mut samples = SeqDirs::new();
+        let mut samples = SeqDirs::new(2);
 
-        samples.parse_csv(&line);
+        samples.parse_csv(line).unwrap();
         assert_eq!("some_animals", samples.id);
         assert_eq!("folder/target/", samples.dir);
     }
@@ -96,9 +399,9 @@ mod test {
     #[test]
     fn split_csv_whitespaces_test() {
         let line = " some_animals,folder/target/ ";
-        let mut samples = SeqDirs::new();
+        let mut samples = SeqDirs::new(2);
 
-        samples.parse_csv(&line);
+        samples.parse_csv(line).unwrap();
         assert_eq!("some_animals", samples.id);
         assert_eq!("folder/target/", samples.dir);
     }
@@ -106,19 +409,110 @@ mod test {
     #[test]
     fn split_ini_test() {
         let line = "some_animals:folder/target/";
-        let mut samples = SeqDirs::new();
+        let mut samples = SeqDirs::new(2);
 
-        samples.parse_ini(&line);
+        samples.parse_ini(line).unwrap();
         assert_eq!("some_animals", samples.id);
         assert_eq!("folder/target/", samples.dir);
     }
 
     #[test]
-    #[should_panic]
-    fn split_ini_panic_test() {
+    fn write_seqdir_test() {
+        let mut sample = SeqDirs::new(2);
+        sample.id = String::from("some_animals");
+        sample.dir = String::from("folder/target/");
+        let samples = vec![sample];
+        let mut csv = Vec::new();
+        let mut ini = Vec::new();
+        write_seqdir(&mut csv, &samples, ConfigFormat::Csv).unwrap();
+        write_seqdir(&mut ini, &samples, ConfigFormat::Ini).unwrap();
+
+        assert_eq!("id,dir\nsome_animals,folder/target/\n", String::from_utf8(csv).unwrap());
+        assert_eq!("[samples]\nsome_animals:folder/target/\n", String::from_utf8(ini).unwrap());
+    }
+
+    #[test]
+    fn split_ini_error_test() {
         let line = "some_animals:folder/target/:random";
-        let mut samples = SeqDirs::new();
+        let mut samples = SeqDirs::new(2);
+
+        let res = samples.parse_ini(line);
+        assert!(matches!(res, Err(Error::Config { line: 2, .. })));
+    }
+
+    #[test]
+    fn sample_sheet_test() {
+        let input = "test_files/sample_sheet.csv";
+        let samples = parse_seqdir(input).unwrap();
+
+        assert_eq!(3, samples.len());
+        assert_eq!("some_animals", samples[0].id);
+        assert_eq!("clean_reads/some_animals/trimmed", samples[0].dir);
+        assert_eq!(None, samples[0].read_1);
+        assert_eq!(4, samples[0].line);
+        assert_eq!("another, animals", samples[1].id);
+        assert_eq!(Some(String::from("another_R1.fq.gz")), samples[1].read_1);
+        assert_eq!(Some(vec![String::from("--careful"), String::from("-k"), String::from("21,33")]), 
+            samples[1].params.args);
+        assert_eq!(Some(16), samples[1].params.memory);
+        assert_eq!(Some(Mode::Isolate), samples[2].params.mode);
+        assert_eq!(Some(4), samples[2].params.threads);
+        assert_eq!(8, samples[2].line);
+    }
+
+    #[test]
+    fn legacy_config_comments_test() {
+        let text = "# Samples\nSamples,Path\n\n# old run\nsome_animals,folder/target/\n";
+        let (samples, errors) = parse_config(text);
+
+        assert!(errors.is_empty());
+        assert_eq!(1, samples.len());
+        assert_eq!(5, samples[0].line);
+    }
+
+    #[test]
+    fn sample_sheet_error_test() {
+        let parse = |text| parse_sample_sheet(text).and_then(get_valid_samples);
+        let unknown = parse("id,dir,reads\nsample,dir,r1.fq\n");
+        let missing = parse("id,dir,r1\nsample,,\n");
+        let orphan = parse("id,dir,r2\n\nsample,dir,r2.fq\n");
+        let mode = parse("id,dir,mode\nsample,dir,metagenome\n");
+        let memory = parse("id,dir,opts,memory\nsample,dir,-m 16,32\n");
+        let opts = parse("id,dir,opts,mode\nsample,dir,--careful,rna\n");
+
+        assert!(matches!(unknown, Err(Error::Config { line: 1, .. })));
+        assert!(matches!(missing, Err(Error::Config { line: 2, .. })));
+        assert!(matches!(orphan, Err(Error::Config { line: 3, .. })));
+        assert!(matches!(mode, Err(Error::Config { line: 2, .. })));
+        assert!(matches!(memory, Err(Error::Config { line: 2, .. })));
+        assert!(matches!(opts, Err(Error::Config { line: 2, .. })));
+    }
+
+    #[test]
+    fn parse_all_lines_test() {
+        let text = "id,dir,threads\nfirst,dir,0\nsecond,dir\nthird,dir,x\n";
+        let (samples, errors) = parse_text(text).unwrap();
 
-        samples.parse_ini(&line);
+        assert_eq!(1, samples.len());
+        assert!(matches!(errors[0], Error::Config { line: 2, .. }));
+        assert!(matches!(errors[1], Error::Config { line: 4, .. }));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn unreadable_line_test() {
+        let path = std::env::temp_dir().join("spr_unreadable_config_test.csv");
+        fs::write(&path, b"id,dir\nfirst,dir\n\xff\xfe,dir\nthird,dir\n").unwrap();
+        let input = path.to_string_lossy();
+        let res = parse_seqdir(&input);
+        let (samples, errors) = parse_seqdir_lines(&input).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        match res {
+            Err(Error::Config { line, .. }) => assert_eq!(3, line),
+            _ => panic!("EXPECTING CONFIG ERROR"),
+        }
+        assert_eq!(2, samples.len());
+        assert_eq!(1, errors.len());
+        assert_eq!(4, samples[1].line);
+    }
+}
@@ -0,0 +1,203 @@
+// Shows the SPAdes stage while SPAdes is running.
+// We tail spades.log and look for the stage headers, e.g. "===== K21 started.".
+// Falls back to the spinner when stdout is not a terminal.
+use std::fs::File;
+use std::io::{self, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use spinners::{Spinner, Spinners};
+
+use crate::utils;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+lazy_static! {
+    static ref STAGE: Regex = Regex::new(r"=====\s+(.+?)\s+started\.").unwrap();
+    static ref KMER: Regex = Regex::new(r"^K\d+$").unwrap();
+}
+
+pub enum Progress {
+    Stages {
+        stop: Arc<AtomicBool>,
+        handle: JoinHandle<()>,
+    },
+    Spinner(Spinner),
+}
+
+impl Progress {
+    // The label is shown in front of the stage, e.g. [2/10] sample_id.
+    pub fn start(log: &Path, label: &str) -> Self {
+        if !io::stdout().is_terminal() {
+            let msg = "SPAdes is processing...\t".to_string();
+            return Progress::Spinner(Spinner::new(Spinners::Moon, msg));
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let tracker = StageTracker::new(log);
+        let label = label.to_string();
+        let thread_stop = Arc::clone(&stop);
+        let handle = thread::spawn(move || track_stages(tracker, &label, &thread_stop));
+
+        Progress::Stages { stop, handle }
+    }
+
+    pub fn stop(self) {
+        match self {
+            Progress::Stages { stop, handle } => {
+                stop.store(true, Ordering::Relaxed);
+                handle.join().ok();
+            }
+            Progress::Spinner(spin) => spin.stop(),
+        }
+    }
+}
+
+fn track_stages(mut tracker: StageTracker, label: &str, stop: &AtomicBool) {
+    let start = Instant::now();
+    while !stop.load(Ordering::Relaxed) {
+        let finished = tracker.update();
+        print_stages(&finished).ok();
+        print_status(&tracker, label, &start).ok();
+        thread::sleep(POLL_INTERVAL);
+    }
+
+    let mut finished = tracker.update();
+    finished.extend(tracker.finish());
+    print_stages(&finished).ok();
+}
+
+// Replaces the status line with the finished stages.
+fn print_stages(finished: &[(String, Duration)]) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    write!(handle, "\r\x1b[K")?;
+    finished.iter()
+        .try_for_each(|(stage, time)| {
+            writeln!(handle, "{:<24}: {}", stage, utils::parse_duration(time.as_secs()))
+        })?;
+    handle.flush()
+}
+
+fn print_status(tracker: &StageTracker, label: &str, start: &Instant) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    let stage = match &tracker.current {
+        Some((stage, time)) => format!("{} ({})", stage,
+            utils::parse_duration(time.elapsed().as_secs())),
+        None => String::from("Starting SPAdes"),
+    };
+    write!(handle, "\r\x1b[K{} | {} | Total: {}",
+        label, stage, utils::parse_duration(start.elapsed().as_secs()))?;
+    handle.flush()
+}
+
+// Reads the new lines in the log since the last update.
+struct StageTracker {
+    log: PathBuf,
+    offset: u64,
+    partial: String,
+    current: Option<(String, Instant)>,
+}
+
+impl StageTracker {
+    // Skips the old content of the log, e.g. when SPAdes continues a run.
+    fn new(log: &Path) -> Self {
+        Self {
+            log: log.to_path_buf(),
+            offset: log.metadata().map(|m| m.len()).unwrap_or(0),
+            partial: String::new(),
+            current: None,
+        }
+    }
+
+    // Returns the stages that finished since the last update.
+    fn update(&mut self) -> Vec<(String, Duration)> {
+        let lines = self.read_lines().unwrap_or_default();
+        let stages: Vec<String> = lines.iter()
+            .filter_map(|line| self.parse_stage(line))
+            .collect();
+
+        stages.into_iter()
+            .filter_map(|stage| self.current.replace((stage, Instant::now())))
+            .map(|(stage, time)| (stage, time.elapsed()))
+            .collect()
+    }
+
+    fn finish(&mut self) -> Option<(String, Duration)> {
+        self.current.take()
+            .map(|(stage, time)| (stage, time.elapsed()))
+    }
+
+    fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut file = File::open(&self.log)?;
+        // SPAdes rewrites the log when it starts a new run.
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buff = Vec::new();
+        self.offset += file.read_to_end(&mut buff)? as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&buff));
+
+        let mut lines: Vec<String> = self.partial.split('\n')
+            .map(|line| line.to_string())
+            .collect();
+        // The last line may be incomplete.
+        self.partial = lines.pop().unwrap_or_default();
+
+        Ok(lines)
+    }
+
+    fn parse_stage(&self, line: &str) -> Option<String> {
+        let stage = STAGE.captures(line)?.get(1)?.as_str();
+        if KMER.is_match(stage) {
+            Some(format!("Assembly {}", stage))
+        } else {
+            Some(stage.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn parse_stage_test() {
+        let tracker = StageTracker::new(Path::new("test_files/spades_test/spades.log"));
+
+        assert_eq!(Some(String::from("Read error correction")),
+            tracker.parse_stage("===== Read error correction started. "));
+        assert_eq!(Some(String::from("Assembly K33")), tracker.parse_stage("===== K33 started."));
+        assert_eq!(None, tracker.parse_stage("===== Read error correction finished."));
+        assert_eq!(None, tracker.parse_stage("======= SPAdes pipeline finished."));
+    }
+
+    #[test]
+    fn stage_tracker_test() {
+        let log = std::env::temp_dir().join("spr_progress_test.log");
+        fs::write(&log, "===== Read error correction started.\n").unwrap();
+        let mut tracker = StageTracker::new(&log);
+        fs::write(&log, "===== Read error correction started.\n\
+            ===== K21 started.\n===== K33 st").unwrap();
+        let finished = tracker.update();
+
+        assert!(finished.is_empty());
+        assert_eq!("Assembly K21", tracker.current.as_ref().unwrap().0);
+
+        fs::write(&log, "===== Read error correction started.\n\
+            ===== K21 started.\n===== K33 started.\n").unwrap();
+        let finished = tracker.update();
+        fs::remove_file(&log).unwrap();
+
+        assert_eq!("Assembly K21", finished[0].0);
+        assert_eq!("Assembly K33", tracker.finish().unwrap().0);
+    }
+}
@@ -0,0 +1,243 @@
+// Structured dry run output for scripts and pipelines.
+// JSON holds everything in one document.
+// TSV has one row per library, with the system info as comment lines.
+use std::io::{self, Result, Write};
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::error::Error;
+use crate::finder::{SeqLib, SeqReads, Samples};
+use crate::utils::SystemInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub fn new(format: &str) -> std::result::Result<Self, Error> {
+        match format {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(Error::InvalidInput(format!("UNSUPPORTED OUTPUT FORMAT: {}", format))),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct DryRunReport {
+    system: SystemInfo,
+    samples: Vec<SampleReport>,
+    failed: Vec<FailedReport>,
+}
+
+#[derive(Serialize)]
+struct SampleReport {
+    id: String,
+    dir: String,
+    libraries: Vec<LibraryReport>,
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct LibraryReport {
+    lib_type: String,
+    read_1: Vec<ReadFile>,
+    read_2: Vec<ReadFile>,
+    singleton: Vec<ReadFile>,
+}
+
+#[derive(Serialize)]
+struct ReadFile {
+    path: String,
+    size: Option<u64>,
+}
+
+#[derive(Serialize)]
+struct FailedReport {
+    id: String,
+    error: String,
+}
+
+impl SampleReport {
+    fn new(reads: &SeqReads) -> Self {
+        Self {
+            id: reads.id.clone(),
+            dir: reads.dir.to_string_lossy().to_string(),
+            libraries: reads.libraries.iter().map(LibraryReport::new).collect(),
+            warnings: reads.get_warnings(),
+        }
+    }
+}
+
+impl LibraryReport {
+    fn new(lib: &SeqLib) -> Self {
+        Self {
+            lib_type: lib.lib_type.as_str().to_string(),
+            read_1: get_read_files(&lib.read_1),
+            read_2: get_read_files(&lib.read_2),
+            singleton: get_read_files(&lib.singleton),
+        }
+    }
+}
+
+fn get_read_files(paths: &[PathBuf]) -> Vec<ReadFile> {
+    paths.iter()
+        .map(|p| ReadFile {
+            path: p.to_string_lossy().to_string(),
+            size: p.metadata().ok().map(|m| m.len()),
+        })
+        .collect()
+}
+
+pub fn write_dryrun(samples: &Samples, format: OutputFormat) -> Result<()> {
+    let report = DryRunReport {
+        system: SystemInfo::new(),
+        samples: samples.reads.iter().map(SampleReport::new).collect(),
+        failed: samples.failed.iter()
+            .map(|(id, e)| FailedReport { id: id.clone(), error: e.to_string() })
+            .collect(),
+    };
+
+    let out = io::stdout();
+    let mut handle = io::BufWriter::new(out);
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut handle, &report)?;
+            writeln!(handle)?;
+        }
+        OutputFormat::Tsv => write_tsv(&mut handle, &report)?,
+        OutputFormat::Text => (),
+    }
+
+    handle.flush()
+}
+
+#[derive(Default, Serialize)]
+struct TsvRow {
+    id: String,
+    dir: String,
+    library: Option<usize>,
+    lib_type: String,
+    read_1: String,
+    read_1_size: String,
+    read_2: String,
+    read_2_size: String,
+    singleton: String,
+    singleton_size: String,
+    warnings: String,
+    error: String,
+}
+
+impl TsvRow {
+    fn new(sample: &SampleReport, library: usize, lib: &LibraryReport) -> Self {
+        let (read_1, read_1_size) = join_read_files(&lib.read_1);
+        let (read_2, read_2_size) = join_read_files(&lib.read_2);
+        let (singleton, singleton_size) = join_read_files(&lib.singleton);
+
+        Self {
+            id: sample.id.clone(),
+            dir: sample.dir.clone(),
+            library: Some(library),
+            lib_type: lib.lib_type.clone(),
+            read_1,
+            read_1_size,
+            read_2,
+            read_2_size,
+            singleton,
+            singleton_size,
+            warnings: sample.warnings.join(";"),
+            error: String::new(),
+        }
+    }
+}
+
+// Multiple files in the same column are separated by semicolons.
+fn join_read_files(files: &[ReadFile]) -> (String, String) {
+    let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+    let sizes: Vec<String> = files.iter()
+        .map(|f| f.size.map(|s| s.to_string()).unwrap_or_default())
+        .collect();
+
+    (paths.join(";"), sizes.join(";"))
+}
+
+fn write_tsv<W: Write>(handle: &mut W, report: &DryRunReport) -> Result<()> {
+    let sys = &report.system;
+    writeln!(handle, "# os\t{}", sys.os)?;
+    writeln!(handle, "# os_version\t{}", sys.os_version)?;
+    writeln!(handle, "# kernel_version\t{}", sys.kernel_version)?;
+    writeln!(handle, "# cores\t{}", sys.cores)?;
+    writeln!(handle, "# threads\t{}", sys.threads)?;
+    writeln!(handle, "# total_ram_gb\t{}", sys.total_ram_gb)?;
+
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(b'\t')
+        .from_writer(handle);
+    let mut rows = report.samples.iter()
+        .flat_map(|s| {
+            s.libraries.iter()
+                .enumerate()
+                .map(move |(i, lib)| TsvRow::new(s, i + 1, lib))
+        })
+        .chain(report.failed.iter()
+            .map(|f| TsvRow {
+                id: f.id.clone(),
+                error: f.error.clone(),
+                ..Default::default()
+            }));
+    rows.try_for_each(|r| writer.serialize(r))
+        .map_err(io::Error::other)?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::finder::{self, AutoSearch, ReadPatterns};
+
+    fn get_report() -> DryRunReport {
+        let samples = finder::auto_find_cleaned_fastq(&AutoSearch::new("test_files", "trimmed_test").unwrap(),
+            &ReadPatterns::default());
+
+        DryRunReport {
+            system: SystemInfo::new(),
+            samples: samples.reads.iter().map(SampleReport::new).collect(),
+            failed: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn output_format_test() {
+        assert_eq!(OutputFormat::Tsv, OutputFormat::new("tsv").unwrap());
+        assert!(OutputFormat::new("xml").is_err());
+    }
+
+    #[test]
+    fn json_report_test() {
+        let report = get_report();
+        let json = serde_json::to_string(&report).unwrap();
+
+        assert!(json.contains("\"id\":\"trimmed_test\""));
+        assert!(json.contains("\"size\":0"));
+        assert!(json.contains("EMPTY FILE"));
+    }
+
+    #[test]
+    fn tsv_report_test() {
+        let report = get_report();
+        let mut buff = Vec::new();
+        write_tsv(&mut buff, &report).unwrap();
+        let tsv = String::from_utf8(buff).unwrap();
+        let lines: Vec<&str> = tsv.lines().collect();
+
+        assert!(lines[0].starts_with("# os\t"));
+        assert!(lines[6].starts_with("id\tdir\tlibrary\tlib_type\tread_1\tread_1_size\t"));
+        assert!(lines[7].starts_with("trimmed_test\ttest_files/trimmed_test\t1\tpaired-end\t"));
+        assert_eq!(8, lines.len());
+    }
+}
@@ -1,14 +1,39 @@
-use std::fs;
+use std::fs::{self, OpenOptions};
 use std::io::{self, Result, Write};
-use std::os::unix;
-use std::process::{Command, Output};
+use std::os::unix::{self, process::ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::str;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
-use spinners::{Spinner, Spinners};
+use rayon::prelude::*;
 
-use crate::finder::SeqReads;
+use crate::cleaner::{self, CleanOpts};
+use crate::dataset;
+use crate::decompressor;
+use crate::error::Error;
+use crate::finder::{Samples, SeqReads};
+use crate::logger::{self, Logger};
+use crate::params::{self, Mode, SampleParams};
+use crate::progress::Progress;
+use crate::stats::{self, AssemblyStats};
+use crate::summary::{self, SummaryRecord};
 use crate::utils;
+use crate::validator;
+
+fn get_spades_version(mode: &Mode) -> Option<String> {
+    let out = Command::new(mode.executable())
+        .arg("--version")
+        .output()
+        .ok()?;
+    let version = String::from_utf8_lossy(&out.stdout).trim().to_string();
+
+    if version.is_empty() {
+        None
+    } else {
+        Some(version)
+    }
+}
 
 pub fn check_spades() {
     let out = Command::new("spades.py")
@@ -21,22 +46,285 @@ pub fn check_spades() {
     }
 }
 
-pub fn assemble_reads(
-    reads: &[SeqReads], 
-    threads: &Option<usize>,
-    outdir: &Option<PathBuf>,
-    args: &Option<String>
-) {
-    let dir = get_outdir(&outdir);
-    utils::check_dir_exists(&dir);
+pub struct RunnerOpts {
+    pub threads: Option<usize>,
+    pub outdir: Option<PathBuf>,
+    pub args: Option<Vec<String>>,
+    pub mode: Mode,
+    pub jobs: usize,
+    pub resume: bool,
+    pub decompress: bool,
+    // Cleans each sample right after a successful assembly.
+    pub clean: Option<CleanOpts>,
+}
+
+type RunResult = std::result::Result<RunStatus, Error>;
+
+// Number of SPAdes log lines to show when SPAdes fails.
+const LOG_TAIL_LINES: usize = 20;
+
+// Runs every sample even if some of them fail.
+// Returns an error listing the failed samples at the end of the batch.
+pub fn assemble_reads(samples: &Samples, opts: &RunnerOpts) -> std::result::Result<(), Error> {
+    let reads = &samples.reads;
+    check_sample_ids(reads)?;
+    check_mode_inputs(reads, opts)?;
+    check_sample_params(reads, opts)?;
+    if !opts.decompress {
+        check_compression(reads)?;
+    }
+    let dir = get_outdir(&opts.outdir);
+    if opts.resume {
+        fs::create_dir_all(&dir)?;
+    } else {
+        utils::check_dir_exists(&dir)?;
+    }
     let contig_dir = dir.join("contig_symlinks");
-    fs::create_dir_all(&contig_dir).unwrap();
+    fs::create_dir_all(&contig_dir)?;
+    let logger = Logger::new(&dir)?;
+    logger.log(&format!("Starting a batch of {} samples. Output: {}", 
+        reads.len(), dir.to_string_lossy()))?;
     println!("\x1b[0;33mTotal samples: {}\n\x1b[0m", reads.len());
-    reads.iter()
-        .for_each(|r| {
-            let mut run = Runner::new(&dir, &contig_dir, r, threads, args);
-            run.run_spades();
+    utils::print_failed_samples(&mut io::stdout(), &samples.failed)?;
+    samples.failed.iter()
+        .try_for_each(|(id, e)| logger.log(&format!("{}: FAILED. {}", id, e)))?;
+
+    let runs = if opts.jobs > 1 {
+        assemble_reads_parallel(reads, &dir, &contig_dir, opts, &logger)?
+    } else {
+        reads.iter()
+            .enumerate()
+            .map(|(i, r)| {
+                let mut run = Runner::new(&dir, &contig_dir, r, opts, opts.threads, &logger);
+                run.position = Some((i + 1, reads.len()));
+                let status = run.run_spades();
+                SampleRun { reads: r, status, info: run.info }
+            })
+            .collect()
+    };
+
+    print_assembly_summary(&runs)?;
+    let records = get_summary_records(samples, &runs, get_spades_version(&opts.mode));
+    summary::write_summary(&dir, &records)?;
+    println!("Summary\t\t: {}", dir.join("summary.csv").to_string_lossy());
+    println!("\t\t  {}", dir.join("summary.json").to_string_lossy());
+    println!("Log\t\t: {}\n", dir.join(logger::LOG_FILE).to_string_lossy());
+    if opts.resume {
+        print_resume_summary(&runs)?;
+    }
+
+    let failed: Vec<String> = samples.failed.iter()
+        .map(|(id, _)| id.clone())
+        .chain(runs.iter().filter(|r| r.status.is_err()).map(|r| r.reads.id.clone()))
+        .collect();
+    logger.log(&format!("Batch finished. Succeeded: {}. Failed: {} {:?}", 
+        runs.len() + samples.failed.len() - failed.len(), failed.len(), failed))?;
+    
+    if !failed.is_empty() {
+        return Err(Error::FailedSamples(failed));
+    }
+
+    Ok(())
+}
+
+fn assemble_reads_parallel<'a>(
+    reads: &'a [SeqReads],
+    dir: &Path,
+    contig_dir: &Path,
+    opts: &RunnerOpts,
+    logger: &Logger
+) -> std::result::Result<Vec<SampleRun<'a>>, Error> {
+    let threads = split_threads(&opts.threads, opts.jobs);
+    println!("\x1b[0;33mRunning {} samples at a time using {} threads each...\n\x1b[0m", 
+        opts.jobs, threads.unwrap());
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(opts.jobs)
+        .build()
+        .map_err(|e| Error::InvalidInput(format!("CAN'T CREATE THREAD POOL: {}", e)))?;
+
+    let runs = pool.install(|| {
+        reads.par_iter()
+            .map(|r| {
+                let mut run = Runner::new(dir, contig_dir, r, opts, threads, logger);
+                let (status, out) = run.run_spades_captured();
+                let stdout = io::stdout();
+                let mut handle = stdout.lock();
+                // Keeps the run result even if the terminal is gone.
+                handle.write_all(&out).ok();
+                SampleRun { reads: r, status, info: run.info }
+            })
+            .collect()
+    });
+
+    Ok(runs)
+}
+
+fn print_assembly_summary(runs: &[SampleRun]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut handle = io::BufWriter::new(stdout);
+    let contigs: Vec<(&str, &AssemblyStats)> = runs.iter()
+        .filter_map(|r| r.info.contigs.as_ref().map(|s| (r.reads.id.as_str(), s)))
+        .collect();
+    let scaffolds: Vec<(&str, &AssemblyStats)> = runs.iter()
+        .filter_map(|r| r.info.scaffolds.as_ref().map(|s| (r.reads.id.as_str(), s)))
+        .collect();
+
+    if !contigs.is_empty() {
+        stats::print_stats_table(&mut handle, "Contig Summary", &contigs)?;
+    }
+
+    if !scaffolds.is_empty() {
+        stats::print_stats_table(&mut handle, "Scaffold Summary", &scaffolds)?;
+    }
+
+    Ok(())
+}
+
+fn print_resume_summary(runs: &[SampleRun]) -> Result<()> {
+    let stdout = io::stdout();
+    let mut handle = io::BufWriter::new(stdout);
+    write_resume_summary(&mut handle, runs)
+}
+
+// Counts the samples by their resume status, even if SPAdes failed.
+// The failed ones are listed again in their own row.
+fn write_resume_summary<W: Write>(handle: &mut W, runs: &[SampleRun]) -> Result<()> {
+    writeln!(handle, "\x1b[0;33mResume Summary\x1b[0m")?;
+
+    [RunStatus::Skipped, RunStatus::Continued, RunStatus::Fresh].iter()
+        .try_for_each(|status| {
+            let ids: Vec<&str> = runs.iter()
+                .filter(|r| r.info.status == Some(*status))
+                .map(|r| r.reads.id.as_str())
+                .collect();
+            writeln!(handle, "{:<14}: {} {:?}", status.as_str(), ids.len(), ids)
+        })?;
+
+    let failed: Vec<&str> = runs.iter()
+        .filter(|r| r.status.is_err())
+        .map(|r| r.reads.id.as_str())
+        .collect();
+    writeln!(handle, "{:<14}: {} {:?}", "Failed", failed.len(), failed)?;
+    writeln!(handle)?;
+
+    Ok(())
+}
+
+fn get_summary_records(
+    samples: &Samples, 
+    runs: &[SampleRun], 
+    version: Option<String>
+) -> Vec<SummaryRecord> {
+    let failed = samples.failed.iter()
+        .map(|(id, e)| {
+            let mut record = SummaryRecord::new(id, Path::new(""), &[]);
+            record.status = String::from("Failed");
+            record.error = Some(e.to_string());
+            record
+        });
+
+    let records = runs.iter()
+        .map(|r| {
+            let inputs: Vec<&Path> = r.reads.files().map(|f| f.as_path()).collect();
+            let mut record = SummaryRecord::new(&r.reads.id, &r.reads.dir, &inputs);
+            record.spades_version = version.clone();
+            record.command = r.info.command.clone();
+            record.exit_code = r.info.exit_code;
+            record.wall_time_secs = r.info.wall_time.map(|t| summary::round(t.as_secs_f64()));
+            record.peak_memory_mb = r.info.peak_memory.map(|m| summary::round(m as f64 / 1024.0));
+            match &r.status {
+                Ok(status) => record.status = status.as_str().to_string(),
+                Err(e) => {
+                    record.status = String::from("Failed");
+                    record.error = Some(e.to_string());
+                }
+            }
+            if let Some(stats) = &r.info.contigs {
+                record.set_contig_stats(stats);
+            }
+            if let Some(stats) = &r.info.scaffolds {
+                record.set_scaffold_stats(stats);
+            }
+            record
         });
+
+    failed.chain(records).collect()
+}
+
+// The IDs are joined to the output directory.
+// Hence, we don't allow IDs like ../sample or sample/run.
+fn check_sample_ids(reads: &[SeqReads]) -> std::result::Result<(), Error> {
+    let invalid: Vec<&str> = reads.iter()
+        .filter(|r| !validator::is_safe_id(&r.id))
+        .map(|r| r.id.as_str())
+        .collect();
+
+    if !invalid.is_empty() {
+        return Err(Error::InvalidInput(format!("UNSAFE CHARACTERS IN THE SAMPLE IDS: {:?}. \
+            PLEASE USE LETTERS, NUMBERS, '.', '_', OR '-' ONLY", invalid)));
+    }
+
+    Ok(())
+}
+
+// Metagenome mode only accepts a single paired-end library.
+// The mode can also come from a pipeline flag in the params.
+fn check_mode_inputs(reads: &[SeqReads], opts: &RunnerOpts) -> std::result::Result<(), Error> {
+    let invalid: Vec<&str> = reads.iter()
+        .filter(|r| get_sample_mode(r, opts).requires_paired_reads())
+        .filter(|r| !r.is_single_library())
+        .map(|r| r.id.as_str())
+        .collect();
+
+    if !invalid.is_empty() {
+        return Err(Error::InvalidInput(format!("META MODE REQUIRES A SINGLE PAIRED-END LIBRARY. \
+            INVALID SAMPLES: {:?}", invalid)));
+    }
+
+    Ok(())
+}
+
+fn get_sample_mode(reads: &SeqReads, opts: &RunnerOpts) -> Mode {
+    let mode = reads.params.mode.unwrap_or(opts.mode);
+    match reads.params.args.as_ref().or(opts.args.as_ref()) {
+        Some(args) => params::get_effective_mode(args, &mode),
+        None => mode,
+    }
+}
+
+// The sample params may not match the batch mode, and vice versa.
+fn check_sample_params(reads: &[SeqReads], opts: &RunnerOpts) -> std::result::Result<(), Error> {
+    reads.iter()
+        .filter(|r| r.params != SampleParams::default())
+        .try_for_each(|r| {
+            let mode = r.params.mode.unwrap_or(opts.mode);
+            match r.params.args.as_ref().or(opts.args.as_ref()) {
+                Some(args) => params::check_mode_params(args, &mode)
+                    .map_err(|e| Error::InvalidInput(format!("{}: {}", r.id, e))),
+                None => Ok(()),
+            }
+        })
+}
+
+fn check_compression(reads: &[SeqReads]) -> std::result::Result<(), Error> {
+    let files = decompressor::get_unsupported_files(reads);
+
+    if !files.is_empty() {
+        let files: Vec<String> = files.iter()
+            .map(|f| format!("  {}", f.to_string_lossy()))
+            .collect();
+        return Err(Error::InvalidInput(format!("SPAdes CAN'T READ BZIP2 OR ZSTD FILES. \
+            PLEASE USE --decompress TO DECOMPRESS THEM BEFORE THE RUN:\n{}", files.join("\n"))));
+    }
+
+    Ok(())
+}
+
+// Splits the thread budget evenly across concurrent jobs.
+// Uses all available threads if the user does not specify it.
+fn split_threads(threads: &Option<usize>, jobs: usize) -> Option<usize> {
+    let total = threads.unwrap_or_else(num_cpus::get);
+    Some(std::cmp::max(1, total / jobs))
 }
 
 fn get_outdir(outdir: &Option<PathBuf>) -> PathBuf {
@@ -46,149 +334,455 @@ fn get_outdir(outdir: &Option<PathBuf>) -> PathBuf {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RunStatus {
+    Fresh,
+    Continued,
+    Skipped,
+}
 
-struct Runner<'a> {
+impl RunStatus {
+    fn as_str(&self) -> &str {
+        match self {
+            RunStatus::Fresh => "Started fresh",
+            RunStatus::Continued => "Continued",
+            RunStatus::Skipped => "Skipped",
+        }
+    }
+}
+
+// The outcome of a sample run for the batch summary.
+struct SampleRun<'a> {
     reads: &'a SeqReads,
+    status: RunResult,
+    info: RunInfo,
+}
+
+// Details of a SPAdes run. Skipped samples only have the assembly stats.
+#[derive(Default)]
+struct RunInfo {
+    // Kept apart from the run result to count failed resumed samples.
+    status: Option<RunStatus>,
+    command: Option<String>,
+    exit_code: Option<i32>,
+    wall_time: Option<Duration>,
+    // In kilobytes.
+    peak_memory: Option<u64>,
+    contigs: Option<AssemblyStats>,
+    scaffolds: Option<AssemblyStats>,
+}
+
+struct Runner<'a> {
+    reads: SeqReads,
     output: PathBuf,
     symlink_dir: &'a Path,
-    threads: &'a Option<usize>,
-    args: &'a Option<String> 
+    opts: &'a RunnerOpts,
+    // The sample settings override the batch settings.
+    mode: Mode,
+    args: Option<Vec<String>>,
+    threads: Option<usize>,
+    memory: Option<usize>,
+    logger: &'a Logger,
+    // The sample number and the total samples in the batch.
+    position: Option<(usize, usize)>,
+    info: RunInfo,
 }
 
 impl<'a> Runner<'a> {
     fn new(
         dir: &Path, 
         contig_dir: &'a Path, 
-        input: &'a SeqReads, 
-        threads: &'a Option<usize>,
-        args: &'a Option<String>
+        input: &SeqReads, 
+        opts: &'a RunnerOpts,
+        threads: Option<usize>,
+        logger: &'a Logger
     ) -> Self {
+        let params = &input.params;
+        let args = params.args.as_ref()
+            .or(opts.args.as_ref())
+            .map(|args| match params.memory {
+                Some(_) => params::remove_memory_params(args),
+                None => args.clone(),
+            });
+
         Self {
-            reads: input,
+            reads: input.clone(),
             output: dir.join(&input.id),
             symlink_dir: contig_dir,
-            threads,
-            args
+            opts,
+            mode: params.mode.unwrap_or(opts.mode),
+            args,
+            threads: params.threads.or(threads),
+            memory: params.memory,
+            logger,
+            position: None,
+            info: RunInfo::default(),
+        }
+    }
+
+    fn run_spades(&mut self) -> RunResult {
+        let stdout = io::stdout();
+        let mut handle = io::BufWriter::new(stdout);
+        let status = self.run(&mut handle, true);
+        if let Err(e) = &status {
+            utils::print_error(&mut handle, e)?;
+        }
+        handle.flush()?;
+        self.log_result(&status)?;
+
+        status
+    }
+
+    // Captures the console output of a run,
+    // so concurrent jobs do not interleave in the terminal.
+    fn run_spades_captured(&mut self) -> (RunResult, Vec<u8>) {
+        let mut buff = Vec::new();
+        let status = self.run(&mut buff, false);
+        if let Err(e) = &status {
+            utils::print_error(&mut buff, e).ok();
+        }
+        self.log_result(&status).ok();
+
+        (status, buff)
+    }
+
+    fn log_result(&self, status: &RunResult) -> Result<()> {
+        let msg = match (status, self.info.wall_time) {
+            (Ok(_), Some(time)) => format!("{}: DONE in {:.2?}", self.reads.id, time),
+            (Ok(status), None) => format!("{}: {}", self.reads.id, status.as_str()),
+            (Err(e), _) => format!("{}: FAILED. {}", self.reads.id, e),
+        };
+
+        self.logger.log(&msg)
+    }
+
+    fn run<W: Write>(&mut self, handle: &mut W, spinner: bool) -> RunResult {
+        let status = self.get_run_status();
+        self.info.status = Some(status);
+        self.logger.log(&format!("{}: {}", self.reads.id, status.as_str()))?;
+        utils::print_header(handle, &self.reads.id)?;
+        self.print_settings(&status, handle)?;
+
+        if status == RunStatus::Skipped {
+            writeln!(handle)?;
+        } else {
+            self.prepare_reads(handle)?;
+            self.print_command(&status, handle)?;
+            let out = if spinner {
+                handle.flush()?;
+                let progress = self.start_progress();
+                let out = self.call_spades(&status);
+                progress.stop();
+                out
+            } else {
+                self.call_spades(&status)
+            };
+            self.remove_tmp_reads()?;
+            self.check_spades_success(&out?, handle)?;
+        }
+
+        self.create_symlink(handle)?;
+        if let Some(clean) = &self.opts.clean {
+            cleaner::clean_sample(&self.output, clean, handle)?;
         }
+        self.print_assembly_stats(handle)?;
+        handle.flush()?;
+
+        Ok(status)
+    }
+
+    // A finished run has both the contigs and a complete spades.log.
+    // Any other run with a spades.log can be picked up by SPAdes --continue.
+    fn get_run_status(&self) -> RunStatus {
+        let log = self.output.join("spades.log");
+
+        if !self.opts.resume || !log.is_file() {
+            RunStatus::Fresh
+        } else if self.output.join("contigs.fasta").is_file() 
+            && utils::is_spades_finished(&log) {
+            RunStatus::Skipped
+        } else {
+            RunStatus::Continued
+        }
+    }
+
+    // Decompresses the reads SPAdes cannot read.
+    fn prepare_reads<W: Write>(&mut self, handle: &mut W) -> Result<()> {
+        if self.opts.decompress {
+            let tmp_dir = self.get_tmp_reads_dir();
+            let count = decompressor::decompress_reads(&mut self.reads, &tmp_dir)?;
+            if count > 0 {
+                writeln!(handle, "Decompressed\t: {} files to {}", 
+                    count, tmp_dir.to_string_lossy())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_tmp_reads_dir(&self) -> PathBuf {
+        self.output.join("tmp_reads")
     }
 
-    fn run_spades(&mut self) {
-        utils::print_header(&self.reads.id);
-        self.print_settings().unwrap();
-        let spin = self.set_spinner();
-        let out = self.call_spades();
-        self.check_spades_success(&out);
-        spin.stop();
-        self.create_symlink();
+    fn remove_tmp_reads(&self) -> Result<()> {
+        let tmp_dir = self.get_tmp_reads_dir();
+        if tmp_dir.is_dir() {
+            fs::remove_dir_all(&tmp_dir)?;
+        }
+
+        Ok(())
     }
 
-    fn check_spades_success(&self, out: &Output) {
-        if !out.status.success() {
-            println!();
-            io::stdout().write_all(&out.stdout).unwrap();
-            io::stdout().write_all(&out.stderr).unwrap();
+    fn check_spades_success<W: Write>(
+        &self, 
+        status: &ExitStatus, 
+        handle: &mut W
+    ) -> std::result::Result<(), Error> {
+        if !status.success() {
+            // The SPAdes error is more useful than a missing log.
+            if let Err(e) = self.print_log_tail(handle) {
+                writeln!(handle, "\x1b[0;33mWarning\t\t: CAN'T READ {}. {}\x1b[0m", 
+                    self.get_log_path().to_string_lossy(), e)?;
+            }
+            return Err(self.get_spades_error(status.code()));
         }
+
+        Ok(())
+    }
+
+    // Shows the end of the SPAdes output, where SPAdes reports the errors.
+    fn print_log_tail<W: Write>(&self, handle: &mut W) -> Result<()> {
+        let log = fs::read_to_string(self.get_log_path())?;
+        let lines: Vec<&str> = log.lines().collect();
+        let start = lines.len().saturating_sub(LOG_TAIL_LINES);
+
+        lines[start..].iter()
+            .try_for_each(|line| writeln!(handle, "{}", line))?;
+        writeln!(handle)?;
+
+        Ok(())
     }
 
-    fn call_spades(&self) -> Output {
-        let mut out = Command::new("spades.py");
-            
-        out.arg("--pe1-1")
-            .arg(&self.reads.read_1)
-            .arg("--pe1-2")
-            .arg(&self.reads.read_2)
-            .arg("-o")
-            .arg(&self.output.clone());
+    fn get_log_path(&self) -> PathBuf {
+        self.output.join(logger::LOG_FILE)
+    }
+
+    fn get_spades_error(&self, code: Option<i32>) -> Error {
+        Error::Spades { id: self.reads.id.clone(), code, log: self.get_log_path() }
+    }
+
+    // Streams the SPAdes stdout and stderr to the sample log.
+    fn call_spades(&mut self, status: &RunStatus) -> std::result::Result<ExitStatus, Error> {
+        fs::create_dir_all(&self.output)?;
+        if *status == RunStatus::Fresh && !self.reads.is_single_pair() {
+            dataset::write_dataset(&self.reads, &self.get_dataset_path())?;
+        }
+
+        let command = self.get_command_line(status);
+        let mut log = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.get_log_path())?;
+        logger::write_log(&mut log, &format!("Command: {}", command))?;
+        self.logger.log(&format!("{}: Command: {}", self.reads.id, command))?;
+        self.info.command = Some(command);
+
+        let time = Instant::now();
+        let child = Command::new(self.mode.executable())
+            .args(self.get_command_args(status))
+            .stdout(Stdio::from(log.try_clone()?))
+            .stderr(Stdio::from(log))
+            .spawn()?;
+        let (exit_status, peak_memory) = wait_with_usage(child)?;
+        self.info.wall_time = Some(time.elapsed());
+        self.info.peak_memory = Some(peak_memory);
+        self.info.exit_code = exit_status.code();
+
+        Ok(exit_status)
+    }
+
+    fn get_command_line(&self, status: &RunStatus) -> String {
+        format!("{} {}", self.mode.executable(), 
+            shell_words::join(self.get_command_args(status)))
+    }
+
+    fn get_command_args(&self, status: &RunStatus) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if *status == RunStatus::Continued {
+            self.get_continue_args(&mut args);
+            return args;
+        }
+
+        if self.reads.is_single_pair() {
+            self.get_pair_args(&mut args);
+        } else {
+            args.push(String::from("--dataset"));
+            args.push(self.get_dataset_path().to_string_lossy().to_string());
+        }
+
+        args.push(String::from("-o"));
+        args.push(self.output.to_string_lossy().to_string());
+
+        if let Some(flag) = self.mode.flag() {
+            args.push(String::from(flag));
+        }
         
-        self.get_spades_args(&mut out);
+        self.get_spades_args(&mut args);
 
-        if self.reads.singleton.is_some() {
-            self.get_singleton(&mut out);
+        if let Some(memory) = self.memory {
+            args.push(String::from("-m"));
+            args.push(memory.to_string());
         }
 
         if self.threads.is_some() {
-            self.get_thread_num(&mut out);
+            self.get_thread_num(&mut args);
+        }
+
+        args
+    }
+
+    fn get_pair_args(&self, args: &mut Vec<String>) {
+        let lib = &self.reads.libraries[0];
+        args.push(String::from("--pe1-1"));
+        args.push(lib.read_1[0].to_string_lossy().to_string());
+        args.push(String::from("--pe1-2"));
+        args.push(lib.read_2[0].to_string_lossy().to_string());
+
+        if let Some(singleton) = lib.singleton.first() {
+            args.push(String::from("--pe1-s"));
+            args.push(singleton.to_string_lossy().to_string());
         }
+    }
+
+    fn get_dataset_path(&self) -> PathBuf {
+        self.output.join("dataset.yaml")
+    }
 
-        out.output().unwrap()
+    fn get_continue_args(&self, args: &mut Vec<String>) {
+        args.push(String::from("--continue"));
+        args.push(String::from("-o"));
+        args.push(self.output.to_string_lossy().to_string());
     }
 
-    fn get_spades_args(&self, out: &mut Command) {
+    fn get_spades_args(&self, args: &mut Vec<String>) {
         if self.args.is_some() {
-            self.get_opt_args(out);
+            self.get_opt_args(args);
         } else {
-            self.get_default_args(out);
+            self.get_default_args(args);
         }
     }
     
-    fn get_default_args(&self, out: &mut Command) {
-        out.arg("--careful");
-    }
-
-    fn get_opt_args(&self, out: &mut Command) {
-        out.arg(self.args.as_ref().unwrap());
+    fn get_default_args(&self, args: &mut Vec<String>) {
+        if self.mode.allows_careful() {
+            args.push(String::from("--careful"));
+        }
     }
 
-    fn get_singleton(&self, out: &mut Command) {
-        out.arg("--pe1-s")
-            .arg(self.reads.singleton.as_ref().unwrap());
+    fn get_opt_args(&self, args: &mut Vec<String>) {
+        args.extend(self.args.as_ref().unwrap().iter().cloned());
     }
 
-    fn get_thread_num(&self, out: &mut Command) {
-        out.arg("--threads")
-            .arg(self.threads.as_ref().unwrap().to_string());
+    fn get_thread_num(&self, args: &mut Vec<String>) {
+        args.push(String::from("--threads"));
+        args.push(self.threads.as_ref().unwrap().to_string());
     }
  
-    fn set_spinner(&mut self) -> Spinner {
-        let msg = "SPAdes is processing...\t".to_string();
-        
-        Spinner::new(Spinners::Moon, msg)
+    fn start_progress(&self) -> Progress {
+        let label = match self.position {
+            Some((index, total)) => format!("[{}/{}] {}", index, total, self.reads.id),
+            None => self.reads.id.clone(),
+        };
+
+        Progress::start(&self.output.join("spades.log"), &label)
     }
 
-    fn print_settings(&self) -> Result<()> {
-        let stdout = io::stdout();
-        let mut buff = io::BufWriter::new(stdout);
-        
+    fn print_settings<W: Write>(&self, status: &RunStatus, buff: &mut W) -> Result<()> {
         writeln!(buff, "ID\t\t: {}", &self.reads.id)?;
-        writeln!(buff, "Input R1\t: {}", &self.reads.read_1.to_string_lossy())?;
-        writeln!(buff, "Input R2\t: {}", &self.reads.read_2.to_string_lossy())?;
+        self.reads.write_reads(buff)?;
+
+        writeln!(buff, "Output\t\t: {}", &self.output.to_string_lossy())?;
 
-        if self.reads.singleton.is_some() {
-            writeln!(buff, "Singleton\t: {}", 
-                &self.reads.singleton.as_ref().unwrap().to_string_lossy())?;
+        if let Some(threads) = self.threads {
+            writeln!(buff, "Threads\t\t: {}", threads)?;
         }
 
-        writeln!(buff, "Output\t\t: {}", &self.output.to_string_lossy())?;
+        if let Some(memory) = self.memory {
+            writeln!(buff, "Memory\t\t: {} Gb", memory)?;
+        }
 
-        if self.args.is_some() {
-            writeln!(buff, "Opt params\t: {}", &self.args.as_ref().unwrap())?;
+        writeln!(buff, "Mode\t\t: {}", self.mode.as_str())?;
+
+        if let Some(args) = &self.args {
+            writeln!(buff, "Opt params\t: {}", shell_words::join(args))?;
         }
-        
+
+        if self.opts.resume {
+            writeln!(buff, "Status\t\t: {}", status.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    fn print_command<W: Write>(&self, status: &RunStatus, buff: &mut W) -> Result<()> {
+        writeln!(buff, "Command\t\t: {}", self.get_command_line(status))?;
+        writeln!(buff, "Log\t\t: {}", self.get_log_path().to_string_lossy())?;
         writeln!(buff)?;
 
         Ok(())
     }
 
-    fn create_symlink(&self) {
+    fn create_symlink<W: Write>(&self, handle: &mut W) -> std::result::Result<(), Error> {
         let contig_sym = format!("{}_contigs.fasta", self.reads.id);
         let contigs_path = self.output.join("contigs.fasta");
 
-        if contigs_path.is_file() {
-            let path = contigs_path.canonicalize().unwrap();
-            let symlink = self.symlink_dir.join(contig_sym);
-            unix::fs::symlink(&path, &symlink).unwrap();
-            utils::print_done().unwrap();
-            self.print_contig_path(&contigs_path, &symlink).unwrap();
-        } else {
-            eprintln!("\x1b[41m[ERROR]\x1b[0m \
-                SPAdes HAS FAILED. PLEASE CHECK SPAdes OUTPUT ABOVE FOR DETAILS.\n");
+        if !contigs_path.is_file() {
+            return Err(self.get_spades_error(None));
+        }
+
+        let path = contigs_path.canonicalize()?;
+        let symlink = self.symlink_dir.join(contig_sym);
+        if symlink.symlink_metadata().is_ok() {
+            fs::remove_file(&symlink)?;
         }
+        unix::fs::symlink(&path, &symlink)?;
+        utils::print_done(handle)?;
+        self.print_contig_path(&contigs_path, &symlink, handle)?;
+
+        Ok(())
     }
 
-    fn print_contig_path(&self, path: &Path, symlink: &Path) -> Result<()>{
-        let stdout = io::stdout();
-        let mut handle = io::BufWriter::new(stdout);
 
+    fn print_assembly_stats<W: Write>(&mut self, handle: &mut W) -> Result<()> {
+        self.info.contigs = self.get_assembly_stats("contigs.fasta")?;
+        self.info.scaffolds = self.get_assembly_stats("scaffolds.fasta")?;
+
+        if let Some(stats) = &self.info.contigs {
+            writeln!(handle, "Contig Stats")?;
+            stats.write_stats(handle)?;
+            writeln!(handle)?;
+        }
+
+        if let Some(stats) = &self.info.scaffolds {
+            writeln!(handle, "Scaffold Stats")?;
+            stats.write_stats(handle)?;
+            writeln!(handle)?;
+        }
+
+        Ok(())
+    }
+
+    // Some SPAdes pipelines, such as rna, do not produce scaffolds.
+    fn get_assembly_stats(&self, fname: &str) -> Result<Option<AssemblyStats>> {
+        let path = self.output.join(fname);
+        if !path.is_file() {
+            return Ok(None);
+        }
+
+        Ok(Some(AssemblyStats::from_fasta(&path)?))
+    }
+
+    fn print_contig_path<W: Write>(&self, path: &Path, symlink: &Path, handle: &mut W) -> Result<()>{
         writeln!(handle)?;
         writeln!(handle, "Contig Path")?;
         writeln!(handle, "File\t\t: {}", path.to_string_lossy())?;
@@ -200,9 +794,73 @@ impl<'a> Runner<'a> {
 }
 
 
+// Waits for SPAdes and gets its peak memory from the kernel.
+// Child::wait does not report the resource usage.
+fn wait_with_usage(child: Child) -> Result<(ExitStatus, u64)> {
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    // SAFETY: rusage is a plain C struct. All zeroes is a valid value.
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+
+    loop {
+        // SAFETY: the pid belongs to our child that nobody else waits for.
+        let res = unsafe { libc::wait4(pid, &mut status, 0, &mut usage) };
+        if res >= 0 {
+            break;
+        }
+        let err = io::Error::last_os_error();
+        if err.kind() != io::ErrorKind::Interrupted {
+            return Err(err);
+        }
+    }
+
+
+    // ru_maxrss is in bytes on macOS and kilobytes elsewhere.
+    let peak_memory = usage.ru_maxrss as u64;
+    let peak_memory = if cfg!(target_os = "macos") { peak_memory / 1024 } else { peak_memory };
+
+    Ok((ExitStatus::from_raw(status), peak_memory))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::finder::{LibType, SeqLib};
+
+    fn get_opts() -> RunnerOpts {
+        RunnerOpts {
+            threads: None,
+            outdir: None,
+            args: None,
+            mode: Mode::Default,
+            jobs: 1,
+            resume: false,
+            decompress: false,
+            clean: None,
+        }
+    }
+
+    // Each test logs to its own directory. Remove it at the end of the test.
+    fn get_logger(name: &str) -> (PathBuf, Logger) {
+        let dir = std::env::temp_dir().join(name);
+        fs::create_dir_all(&dir).unwrap();
+        let logger = Logger::new(&dir).unwrap();
+
+        (dir, logger)
+    }
+
+    fn get_reads(id: &str) -> SeqReads {
+        let mut lib = SeqLib::new(LibType::PairedEnd);
+        lib.read_1 = vec![PathBuf::from("some_seq_R1.fq.gz")];
+        lib.read_2 = vec![PathBuf::from("some_seq_R2.fq.gz")];
+
+        SeqReads {
+            dir: PathBuf::from("test_files/trimmed_test"),
+            id: String::from(id),
+            libraries: vec![lib],
+            params: SampleParams::default(),
+        }
+    }
 
     #[test]
     fn outdir_test() {
@@ -213,4 +871,174 @@ mod test {
         assert_eq!(PathBuf::from(&path), outdir);
     }
 
+    #[test]
+    fn run_status_test() {
+        let dir = Path::new("test_files");
+        let reads = get_reads("spades_test");
+        let (log_dir, logger) = get_logger("spr_runner_run_status_test");
+        let mut opts = get_opts();
+        opts.resume = true;
+        let resume = Runner::new(dir, dir, &reads, &opts, None, &logger);
+        let fresh_opts = get_opts();
+        let fresh = Runner::new(dir, dir, &reads, &fresh_opts, None, &logger);
+
+        assert_eq!(RunStatus::Skipped, resume.get_run_status());
+        assert_eq!(RunStatus::Fresh, fresh.get_run_status());
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn command_args_test() {
+        let dir = Path::new("assemblies");
+        let (log_dir, logger) = get_logger("spr_runner_command_args_test");
+        let reads = get_reads("some_seq");
+        let mut opts = get_opts();
+        opts.args = Some(vec![String::from("--careful"), String::from("-m"), String::from("16")]);
+        let run = Runner::new(dir, dir, &reads, &opts, Some(8), &logger);
+        let args = run.get_command_args(&RunStatus::Fresh);
+
+        let res = "--pe1-1 some_seq_R1.fq.gz --pe1-2 some_seq_R2.fq.gz \
+            -o assemblies/some_seq --careful -m 16 --threads 8";
+        assert_eq!(res, args.join(" "));
+        assert_eq!("--continue -o assemblies/some_seq", 
+            run.get_command_args(&RunStatus::Continued).join(" "));
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn mode_args_test() {
+        let dir = Path::new("assemblies");
+        let (log_dir, logger) = get_logger("spr_runner_mode_args_test");
+        let reads = get_reads("some_seq");
+        let mut opts = get_opts();
+        opts.mode = Mode::Isolate;
+        let run = Runner::new(dir, dir, &reads, &opts, None, &logger);
+        let args = run.get_command_args(&RunStatus::Fresh);
+
+        assert!(args.contains(&String::from("--isolate")));
+        assert!(!args.contains(&String::from("--careful")));
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn sample_params_test() {
+        let dir = Path::new("assemblies");
+        let (log_dir, logger) = get_logger("spr_runner_sample_params_test");
+        let mut reads = get_reads("some_seq");
+        reads.params.mode = Some(Mode::Isolate);
+        reads.params.threads = Some(4);
+        reads.params.memory = Some(32);
+        let mut opts = get_opts();
+        opts.args = Some(vec![String::from("-m"), String::from("16"), String::from("-k"), String::from("21")]);
+        let run = Runner::new(dir, dir, &reads, &opts, Some(8), &logger);
+        let args = run.get_command_args(&RunStatus::Fresh);
+
+        assert_eq!("--pe1-1 some_seq_R1.fq.gz --pe1-2 some_seq_R2.fq.gz \
+            -o assemblies/some_seq --isolate -k 21 -m 32 --threads 4", args.join(" "));
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn sample_params_error_test() {
+        let mut reads = get_reads("some_seq");
+        reads.params.mode = Some(Mode::Rna);
+        let mut opts = get_opts();
+        opts.args = Some(vec![String::from("--careful")]);
+        let err = check_sample_params(&[reads], &opts).unwrap_err();
+
+        assert!(err.to_string().contains("--careful IS NOT COMPATIBLE WITH rna MODE"));
+    }
+
+    #[test]
+    fn unsafe_id_error_test() {
+        let reads = [get_reads("some_seq"), get_reads("../some_seq")];
+        let err = check_sample_ids(&reads).unwrap_err();
+
+        assert!(err.to_string().contains("[\"../some_seq\"]"));
+        assert!(check_sample_ids(&reads[..1]).is_ok());
+    }
+
+    #[test]
+    fn meta_mode_inputs_error_test() {
+        let mut reads = get_reads("some_seq");
+        reads.libraries[0].lib_type = LibType::Single;
+        let mut opts = get_opts();
+        opts.mode = Mode::Meta;
+        let err = check_mode_inputs(&[reads], &opts).unwrap_err();
+
+        assert!(err.to_string().contains("REQUIRES A SINGLE PAIRED-END LIBRARY"));
+    }
+
+    #[test]
+    fn meta_flag_inputs_error_test() {
+        let mut reads = get_reads("some_seq");
+        reads.libraries[0].lib_type = LibType::Single;
+        let mut opts = get_opts();
+        opts.args = Some(vec![String::from("--meta")]);
+        let err = check_mode_inputs(&[reads], &opts).unwrap_err();
+
+        assert!(err.to_string().contains("REQUIRES A SINGLE PAIRED-END LIBRARY"));
+    }
+
+    #[test]
+    fn dataset_args_test() {
+        let dir = Path::new("assemblies");
+        let (log_dir, logger) = get_logger("spr_runner_dataset_args_test");
+        let mut reads = get_reads("some_seq");
+        reads.libraries.push(SeqLib::new(LibType::MatePairs));
+        let opts = get_opts();
+        let run = Runner::new(dir, dir, &reads, &opts, None, &logger);
+        let args = run.get_command_args(&RunStatus::Fresh);
+
+        assert_eq!("--dataset assemblies/some_seq/dataset.yaml -o assemblies/some_seq --careful", 
+            args.join(" "));
+        fs::remove_dir_all(&log_dir).unwrap();
+    }
+
+    #[test]
+    fn resume_summary_test() {
+        let continued = get_reads("continued");
+        let failed = get_reads("failed");
+        let mut runs: Vec<SampleRun> = [&continued, &failed].iter()
+            .map(|r| SampleRun { reads: r, status: Ok(RunStatus::Continued), info: RunInfo::default() })
+            .collect();
+        runs.iter_mut().for_each(|r| r.info.status = Some(RunStatus::Continued));
+        runs[1].status = Err(Error::Spades { 
+            id: String::from("failed"), code: Some(1), log: PathBuf::from("failed/spades-runner.log") 
+        });
+        let mut buff = Vec::new();
+        write_resume_summary(&mut buff, &runs).unwrap();
+        let summary = String::from_utf8(buff).unwrap();
+
+        assert!(summary.contains("Continued     : 2 [\"continued\", \"failed\"]"));
+        assert!(summary.contains("Failed        : 1 [\"failed\"]"));
+    }
+
+    #[test]
+    fn missing_log_error_test() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let dir = Path::new("spr_missing_log_test");
+        let reads = get_reads("sample");
+        let (log_dir, logger) = get_logger("spr_runner_missing_log_test");
+        let opts = get_opts();
+        let run = Runner::new(dir, dir, &reads, &opts, None, &logger);
+        let mut buff = Vec::new();
+        let res = run.check_spades_success(&ExitStatus::from_raw(256), &mut buff);
+        fs::remove_dir_all(&log_dir).unwrap();
+
+        match res {
+            Err(Error::Spades { code, .. }) => assert_eq!(Some(1), code),
+            _ => panic!("EXPECTING SPAdes ERROR"),
+        }
+        assert!(String::from_utf8(buff).unwrap().contains("CAN'T READ"));
+    }
+
+    #[test]
+    fn split_threads_test() {
+        let threads = Some(64);
+        assert_eq!(Some(16), split_threads(&threads, 4));
+        assert_eq!(Some(1), split_threads(&threads, 100));
+    }
+
 }
\ No newline at end of file
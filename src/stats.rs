@@ -0,0 +1,154 @@
+// Summary statistics of SPAdes contigs and scaffolds.
+use std::fs::File;
+use std::io::{BufRead, BufReader, Result, Write};
+use std::path::Path;
+
+// Contig lengths reported in the per-sample summary.
+pub const LENGTH_THRESHOLDS: [usize; 3] = [1000, 5000, 10000];
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AssemblyStats {
+    pub contig_count: usize,
+    pub total_length: usize,
+    pub largest: usize,
+    pub n50: usize,
+    pub n90: usize,
+    pub l50: usize,
+    pub gc: f64,
+    pub above_thresholds: Vec<(usize, usize)>,
+}
+
+impl AssemblyStats {
+    pub fn from_fasta(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        let buff = BufReader::new(file);
+        let mut lengths = Vec::new();
+        let mut gc_count = 0;
+        let mut at_count = 0;
+
+        for line in buff.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.starts_with('>') {
+                lengths.push(0);
+            } else if let Some(len) = lengths.last_mut() {
+                *len += line.len();
+                line.bytes()
+                    .for_each(|b| match b.to_ascii_uppercase() {
+                        b'G' | b'C' => gc_count += 1,
+                        b'A' | b'T' => at_count += 1,
+                        _ => (),
+                    });
+            }
+        }
+
+        let mut stats = Self::from_lengths(lengths);
+        stats.gc = get_gc_content(gc_count, at_count);
+
+        Ok(stats)
+    }
+
+    fn from_lengths(mut lengths: Vec<usize>) -> Self {
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        let total_length = lengths.iter().sum();
+        let (n50, l50) = get_nx(&lengths, total_length, 50);
+        let (n90, _) = get_nx(&lengths, total_length, 90);
+
+        Self {
+            contig_count: lengths.len(),
+            total_length,
+            largest: lengths.first().copied().unwrap_or(0),
+            n50,
+            n90,
+            l50,
+            gc: 0.0,
+            above_thresholds: LENGTH_THRESHOLDS.iter()
+                .map(|&t| (t, lengths.iter().filter(|&&len| len >= t).count()))
+                .collect(),
+        }
+    }
+
+    pub fn write_stats<W: Write>(&self, handle: &mut W) -> Result<()> {
+        writeln!(handle, "Contigs\t\t: {}", self.contig_count)?;
+        writeln!(handle, "Total length\t: {}", self.total_length)?;
+        writeln!(handle, "Largest\t\t: {}", self.largest)?;
+        writeln!(handle, "N50\t\t: {}", self.n50)?;
+        writeln!(handle, "N90\t\t: {}", self.n90)?;
+        writeln!(handle, "L50\t\t: {}", self.l50)?;
+        writeln!(handle, "GC (%)\t\t: {:.2}", self.gc)?;
+        self.above_thresholds.iter()
+            .try_for_each(|(t, count)| writeln!(handle, ">= {} bp\t: {}", t, count))
+    }
+}
+
+// Returns the Nx length and the number of contigs to reach it.
+// The lengths should be sorted in descending order.
+fn get_nx(lengths: &[usize], total_length: usize, x: usize) -> (usize, usize) {
+    let target = total_length * x;
+    let mut sum = 0;
+    for (i, len) in lengths.iter().enumerate() {
+        sum += len;
+        if sum * 100 >= target {
+            return (*len, i + 1);
+        }
+    }
+
+    (0, 0)
+}
+
+// Ignores ambiguous bases, such as N.
+fn get_gc_content(gc_count: usize, at_count: usize) -> f64 {
+    let total = gc_count + at_count;
+    if total == 0 {
+        return 0.0;
+    }
+
+    gc_count as f64 / total as f64 * 100.0
+}
+
+pub fn print_stats_table<W: Write>(
+    handle: &mut W,
+    title: &str,
+    stats: &[(&str, &AssemblyStats)]
+) -> Result<()> {
+    writeln!(handle, "\x1b[0;33m{}\x1b[0m", title)?;
+    writeln!(handle, "{:<20} {:>8} {:>12} {:>10} {:>10} {:>10} {:>6} {:>7}",
+        "ID", "Contigs", "Total length", "Largest", "N50", "N90", "L50", "GC (%)")?;
+    stats.iter()
+        .try_for_each(|(id, s)| {
+            writeln!(handle, "{:<20} {:>8} {:>12} {:>10} {:>10} {:>10} {:>6} {:>7.2}",
+                id, s.contig_count, s.total_length, s.largest, s.n50, s.n90, s.l50, s.gc)
+        })?;
+    writeln!(handle)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn contig_stats_test() {
+        let path = Path::new("test_files/spades_test/contigs.fasta");
+        let stats = AssemblyStats::from_fasta(path).unwrap();
+
+        assert_eq!(4, stats.contig_count);
+        assert_eq!(130, stats.total_length);
+        assert_eq!(60, stats.largest);
+        assert_eq!(40, stats.n50);
+        assert_eq!(20, stats.n90);
+        assert_eq!(2, stats.l50);
+        assert_eq!(50.0, stats.gc);
+        assert_eq!(vec![(1000, 0), (5000, 0), (10000, 0)], stats.above_thresholds);
+    }
+
+    #[test]
+    fn length_thresholds_test() {
+        let stats = AssemblyStats::from_lengths(vec![500, 12000, 1000, 6000]);
+
+        assert_eq!(12000, stats.n50);
+        assert_eq!(1, stats.l50);
+        assert_eq!(vec![(1000, 3), (5000, 2), (10000, 1)], stats.above_thresholds);
+    }
+}
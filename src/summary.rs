@@ -0,0 +1,131 @@
+// Writes the batch summary to summary.csv and summary.json.
+// One record per sample, including the samples that failed.
+use std::fs::File;
+use std::io::{self, BufWriter, Result};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::stats::AssemblyStats;
+
+#[derive(Debug, Default, Serialize)]
+pub struct SummaryRecord {
+    pub id: String,
+    pub dir: String,
+    pub inputs: String,
+    pub command: Option<String>,
+    pub spades_version: Option<String>,
+    pub status: String,
+    pub exit_code: Option<i32>,
+    pub error: Option<String>,
+    pub wall_time_secs: Option<f64>,
+    pub peak_memory_mb: Option<f64>,
+    pub contigs: Option<usize>,
+    pub total_length: Option<usize>,
+    pub largest_contig: Option<usize>,
+    pub n50: Option<usize>,
+    pub n90: Option<usize>,
+    pub l50: Option<usize>,
+    pub gc: Option<f64>,
+    pub contigs_1kb: Option<usize>,
+    pub contigs_5kb: Option<usize>,
+    pub contigs_10kb: Option<usize>,
+    pub scaffolds: Option<usize>,
+    pub scaffold_total_length: Option<usize>,
+    pub scaffold_n50: Option<usize>,
+}
+
+impl SummaryRecord {
+    pub fn new(id: &str, dir: &Path, inputs: &[&Path]) -> Self {
+        let inputs: Vec<String> = inputs.iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+
+        Self {
+            id: id.to_string(),
+            dir: dir.to_string_lossy().to_string(),
+            inputs: inputs.join(";"),
+            ..Default::default()
+        }
+    }
+
+    pub fn set_contig_stats(&mut self, stats: &AssemblyStats) {
+        self.contigs = Some(stats.contig_count);
+        self.total_length = Some(stats.total_length);
+        self.largest_contig = Some(stats.largest);
+        self.n50 = Some(stats.n50);
+        self.n90 = Some(stats.n90);
+        self.l50 = Some(stats.l50);
+        self.gc = Some(round(stats.gc));
+        self.contigs_1kb = get_threshold_count(stats, 1000);
+        self.contigs_5kb = get_threshold_count(stats, 5000);
+        self.contigs_10kb = get_threshold_count(stats, 10000);
+    }
+
+    pub fn set_scaffold_stats(&mut self, stats: &AssemblyStats) {
+        self.scaffolds = Some(stats.contig_count);
+        self.scaffold_total_length = Some(stats.total_length);
+        self.scaffold_n50 = Some(stats.n50);
+    }
+}
+
+fn get_threshold_count(stats: &AssemblyStats, threshold: usize) -> Option<usize> {
+    stats.above_thresholds.iter()
+        .find(|(t, _)| *t == threshold)
+        .map(|(_, count)| *count)
+}
+
+pub fn round(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+pub fn write_summary(dir: &Path, records: &[SummaryRecord]) -> Result<()> {
+    write_csv(&dir.join("summary.csv"), records)?;
+    write_json(&dir.join("summary.json"), records)?;
+
+    Ok(())
+}
+
+fn write_csv(path: &Path, records: &[SummaryRecord]) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    records.iter()
+        .try_for_each(|r| writer.serialize(r))
+        .map_err(io::Error::other)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+fn write_json(path: &Path, records: &[SummaryRecord]) -> Result<()> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(writer, records)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn write_summary_test() {
+        let dir = std::env::temp_dir().join("spr_summary_test");
+        fs::create_dir_all(&dir).unwrap();
+        let stats = AssemblyStats::from_fasta(Path::new("test_files/spades_test/contigs.fasta"))
+            .unwrap();
+        let mut record = SummaryRecord::new("sample", Path::new("reads"),
+            &[Path::new("reads/sample_R1.fq.gz"), Path::new("reads/sample_R2.fq.gz")]);
+        record.set_contig_stats(&stats);
+        write_summary(&dir, &[record]).unwrap();
+
+        let csv = fs::read_to_string(dir.join("summary.csv")).unwrap();
+        let json = fs::read_to_string(dir.join("summary.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(csv.starts_with("id,dir,inputs,command,"));
+        assert!(csv.contains("sample,reads,reads/sample_R1.fq.gz;reads/sample_R2.fq.gz,"));
+        assert!(json.contains("\"n50\": 40"));
+    }
+}
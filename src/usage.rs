@@ -0,0 +1,189 @@
+// Reports where the disk space of SPAdes outputs goes.
+// Uses the same rules as the cleaner to find the reclaimable space.
+use std::io::{self, Result, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::cleaner::{self, CleanOpts};
+use crate::error::Error;
+use crate::report::OutputFormat;
+use crate::utils;
+
+#[derive(Debug, Default, Serialize)]
+struct SampleUsage {
+    id: String,
+    dir: String,
+    finished: bool,
+    corrected_reads: u64,
+    kmer_dirs: u64,
+    misc: u64,
+    final_outputs: u64,
+    reclaimable: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct DiskUsage {
+    samples: Vec<SampleUsage>,
+    // Unfinished runs are not counted. The cleaner skips them.
+    total_reclaimable: u64,
+}
+
+impl SampleUsage {
+    fn new(dir: &Path, opts: &CleanOpts) -> Result<Self> {
+        let mut usage = Self {
+            id: cleaner::get_sample_id(dir)?,
+            dir: dir.to_string_lossy().to_string(),
+            finished: cleaner::check_finished(dir).is_ok(),
+            ..Default::default()
+        };
+
+        cleaner::find_contents(dir, opts)?.iter()
+            .for_each(|p| {
+                let size = cleaner::get_size(p);
+                let name = p.file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                match name.as_str() {
+                    "corrected" => usage.corrected_reads += size,
+                    name if is_kmer_dir(name) && p.is_dir() => usage.kmer_dirs += size,
+                    _ => usage.misc += size,
+                }
+            });
+        usage.reclaimable = usage.corrected_reads + usage.kmer_dirs + usage.misc;
+        usage.final_outputs = cleaner::get_size(dir).saturating_sub(usage.reclaimable);
+
+        Ok(usage)
+    }
+}
+
+// SPAdes names K-mer directories by the K size, e.g. K21, K33.
+fn is_kmer_dir(name: &str) -> bool {
+    name.len() > 1
+        && name.starts_with('K')
+        && name[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+pub fn report_disk_usage(
+    path: &Path,
+    opts: &CleanOpts,
+    format: OutputFormat
+) -> std::result::Result<(), Error> {
+    let dirs = cleaner::find_spades_dirs(path);
+    let samples = get_sample_usage(&dirs, opts, &mut io::stderr())?;
+    let total_reclaimable = samples.iter()
+        .filter(|s| s.finished)
+        .map(|s| s.reclaimable)
+        .sum();
+    let usage = DiskUsage { samples, total_reclaimable };
+    print_usage(&usage, format)?;
+
+    Ok(())
+}
+
+// Unreadable sample directories are skipped with a warning.
+// The warnings go to stderr to keep the JSON output valid.
+fn get_sample_usage<W: Write>(
+    dirs: &[PathBuf], 
+    opts: &CleanOpts, 
+    handle: &mut W
+) -> Result<Vec<SampleUsage>> {
+    let mut samples = Vec::new();
+    for dir in dirs {
+        match SampleUsage::new(dir, opts) {
+            Ok(usage) => samples.push(usage),
+            Err(e) => writeln!(handle, "\x1b[0;33mWarning\t\t: SKIPPED {}. {}\x1b[0m", 
+                dir.to_string_lossy(), e)?,
+        }
+    }
+
+    Ok(samples)
+}
+
+fn print_usage(usage: &DiskUsage, format: OutputFormat) -> Result<()> {
+    let out = io::stdout();
+    let mut handle = io::BufWriter::new(out);
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut handle, usage)?;
+            writeln!(handle)?;
+        }
+        _ => write_usage(&mut handle, usage)?,
+    }
+
+    handle.flush()
+}
+
+fn write_usage<W: Write>(handle: &mut W, usage: &DiskUsage) -> Result<()> {
+    writeln!(handle, "\x1b[0;33mDisk Usage\x1b[0m")?;
+    usage.samples.iter()
+        .try_for_each(|s| {
+            writeln!(handle, "ID\t\t: {}", s.id)?;
+            writeln!(handle, "Dir\t\t: {}", s.dir)?;
+            if !s.finished {
+                writeln!(handle, "\x1b[0;33mStatus\t\t: UNFINISHED. SKIPPED BY CLEAN\x1b[0m")?;
+            }
+            writeln!(handle, "Corrected reads\t: {}", utils::format_size(s.corrected_reads))?;
+            writeln!(handle, "K-mer dirs\t: {}", utils::format_size(s.kmer_dirs))?;
+            writeln!(handle, "Misc\t\t: {}", utils::format_size(s.misc))?;
+            writeln!(handle, "Final outputs\t: {}", utils::format_size(s.final_outputs))?;
+            writeln!(handle, "Reclaimable\t: {}\n", utils::format_size(s.reclaimable))
+        })?;
+
+    writeln!(handle, "Samples\t\t: {}", usage.samples.len())?;
+    writeln!(handle, "Reclaimable\t: {}\n", utils::format_size(usage.total_reclaimable))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn kmer_dir_test() {
+        assert!(is_kmer_dir("K21"));
+        assert!(is_kmer_dir("K127"));
+        assert!(!is_kmer_dir("K"));
+        assert!(!is_kmer_dir("Kmers"));
+    }
+
+    #[test]
+    fn sample_usage_test() {
+        let dir = std::env::temp_dir().join("spr_usage_test");
+        let sample = dir.join("sample");
+        fs::create_dir_all(sample.join("K21")).unwrap();
+        fs::create_dir_all(sample.join("corrected")).unwrap();
+        fs::write(sample.join("spades.log"), "======= SPAdes pipeline finished.").unwrap();
+        fs::write(sample.join("contigs.fasta"), "ACGT").unwrap();
+        fs::write(sample.join("params.txt"), "AC").unwrap();
+        fs::write(sample.join("K21/final_contigs.fasta"), "ACGTAC").unwrap();
+        fs::write(sample.join("corrected/reads.fq"), "ACGTACGT").unwrap();
+
+        let usage = SampleUsage::new(&sample, &CleanOpts::default()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!("sample", usage.id);
+        assert!(usage.finished);
+        assert_eq!(8, usage.corrected_reads);
+        assert_eq!(6, usage.kmer_dirs);
+        assert_eq!(2, usage.misc);
+        assert_eq!(37, usage.final_outputs);
+        assert_eq!(16, usage.reclaimable);
+    }
+
+    #[test]
+    fn unreadable_sample_test() {
+        let dir = std::env::temp_dir().join("spr_usage_unreadable_test");
+        let sample = dir.join("sample");
+        fs::create_dir_all(&sample).unwrap();
+        fs::write(sample.join("spades.log"), "======= SPAdes pipeline finished.").unwrap();
+        let dirs = vec![dir.join("missing"), sample];
+        let mut buff = Vec::new();
+        let samples = get_sample_usage(&dirs, &CleanOpts::default(), &mut buff).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(1, samples.len());
+        assert_eq!("sample", samples[0].id);
+        assert!(String::from_utf8(buff).unwrap().contains("SKIPPED"));
+    }
+}
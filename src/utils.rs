@@ -2,33 +2,66 @@ use std::fs;
 use std::path::Path;
 use std::io::{self, Result, Write};
 
+use serde::Serialize;
 use sysinfo::{System,SystemExt};
-use chrono::NaiveTime;
 
-pub fn check_dir_exists(dir: &Path) {
+use crate::error::Error;
+
+pub fn check_dir_exists(dir: &Path) -> std::result::Result<(), Error> {
     if dir.exists() {
-        panic!("{:?} DIR EXISTS. PLEASE RENAME OR REMOVE IT", dir);
-    } else {
-        fs::create_dir_all(dir)
-            .expect("CAN'T CREATE DIR");
+        return Err(Error::InvalidInput(format!("{:?} DIR EXISTS. PLEASE RENAME OR REMOVE IT", dir)));
+    } 
+    
+    fs::create_dir_all(dir)?;
+
+    Ok(())
+}
+
+pub fn is_spades_finished(log: &Path) -> bool {
+    match fs::read_to_string(log) {
+        Ok(text) => text.contains("SPAdes pipeline finished"),
+        Err(_) => false,
     }
 }
 
-pub fn print_done() -> Result<()> {
-    let stdout = io::stdout();
-    let mut handle = stdout.lock();
+pub fn print_done<W: Write>(handle: &mut W) -> Result<()> {
     writeln!(handle, "\x1b[0;32mDONE!\x1b[0m")?;
 
     Ok(())
 }
 
-fn parse_duration(duration: u64) -> String {
-    let sec = (duration % 60) as u32;
-    let min = ((duration/60) % 60) as u32;
-    let hours = ((duration/60) / 60) as u32;
-    let time = NaiveTime::from_hms(hours, min, sec);
+pub fn print_error<W: Write>(handle: &mut W, err: &Error) -> Result<()> {
+    writeln!(handle, "\x1b[41m[ERROR]\x1b[0m {}\n", err)
+}
+
+pub fn print_failed_samples<W: Write>(handle: &mut W, failed: &[(String, Error)]) -> Result<()> {
+    failed.iter()
+        .try_for_each(|(_, e)| print_error(handle, e))
+}
+
+// SPAdes runs can take more than a day. 
+// Hence, we don't wrap the hours.
+pub fn parse_duration(duration: u64) -> String {
+    let sec = duration % 60;
+    let min = (duration / 60) % 60;
+    let hours = (duration / 60) / 60;
     
-    time.format("%H:%M:%S").to_string()
+    format!("{:02}:{:02}:{:02}", hours, min, sec)
+}
+
+pub fn format_size(bytes: u64) -> String {
+    let units = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < units.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.2} {}", size, units[unit]),
+    }
 }
 
 pub fn print_formatted_duration(duration: u64) {
@@ -42,24 +75,43 @@ pub fn split_strings(lines: &str, sep: char) -> Vec<String> {
         .collect()
 }
 
+#[derive(Debug, Serialize)]
+pub struct SystemInfo {
+    pub os: String,
+    pub os_version: String,
+    pub kernel_version: String,
+    pub cores: usize,
+    pub threads: usize,
+    pub total_ram_gb: u64,
+}
+
+impl SystemInfo {
+    pub fn new() -> Self {
+        let sysinfo = sysinfo::System::new_all();
+        let gb = 1048576;
+
+        Self {
+            os: get_os_name(&sysinfo),
+            os_version: get_os_version(&sysinfo),
+            kernel_version: get_kernel_version(&sysinfo),
+            cores: num_cpus::get_physical(),
+            threads: num_cpus::get(),
+            total_ram_gb: sysinfo.get_total_memory()/gb,
+        }
+    }
+}
+
 pub fn get_system_info() -> Result<()> {
-    let sysinfo = sysinfo::System::new_all();
+    let info = SystemInfo::new();
     let io = io::stdout();
     let mut handle = io::BufWriter::new(io);
 
-    let total_ram = sysinfo.get_total_memory();
-    let gb = 1048576;
-
     writeln!(handle, "\x1b[0;33mSystem Information\x1b[0m")?;
-
-    writeln!(handle, "Operating system\t: {} {}", 
-        get_os_name(&sysinfo),
-        get_os_version(&sysinfo))?;
-
-    writeln!(handle, "Kernel version\t\t: {}", get_kernel_version(&sysinfo))?;
-    writeln!(handle, "Available cores\t\t: {:?}", num_cpus::get_physical())?;
-    writeln!(handle, "Available threads\t: {:?}", num_cpus::get())?;
-    writeln!(handle, "Total RAM\t\t: {} Gb", total_ram/gb)?;
+    writeln!(handle, "Operating system\t: {} {}", info.os, info.os_version)?;
+    writeln!(handle, "Kernel version\t\t: {}", info.kernel_version)?;
+    writeln!(handle, "Available cores\t\t: {:?}", info.cores)?;
+    writeln!(handle, "Available threads\t: {:?}", info.threads)?;
+    writeln!(handle, "Total RAM\t\t: {} Gb", info.total_ram_gb)?;
     writeln!(handle)?;
 
     Ok(())
@@ -86,12 +138,12 @@ fn get_kernel_version(sysinfo: &System) -> String {
     }
 }
 
-pub fn print_header(text: &str) {
+pub fn print_header<W: Write>(handle: &mut W, text: &str) -> Result<()> {
     let header = format!("Processing {}", text);
     let length = 78;
     let sym = '=';
     let mut header = PrettyHeader::new(&header, sym, length);
-    header.print_header().unwrap();
+    header.print_header(handle)
 }
 
 struct PrettyHeader {
@@ -115,16 +167,14 @@ impl PrettyHeader {
         }
     }
 
-    fn print_header(&mut self) -> Result<()> {
+    fn print_header<W: Write>(&mut self, handle: &mut W) -> Result<()> {
         self.get_len();
-        let io = io::stdout();
-        let mut handle = io::BufWriter::new(io);
         write!(handle,"{}", self.color)?;
         
         if self.text_len > self.len {
             writeln!(handle, "{}", self.text)?;
         } else {
-            self.print_with_symbol(&mut handle)?;
+            self.print_with_symbol(handle)?;
         }
         write!(handle,"\x1b[0m")?;
         Ok(())
@@ -174,5 +224,24 @@ mod test {
 
         assert_eq!("00:01:05", time);
         assert_eq!("01:00:00", hours);
+        assert_eq!("25:00:01", parse_duration(90001));
+    }
+
+    #[test]
+    fn format_size_test() {
+        assert_eq!("512 B", format_size(512));
+        assert_eq!("1.50 KB", format_size(1536));
+        assert_eq!("2.00 GB", format_size(2 * 1024 * 1024 * 1024));
+    }
+
+    #[test]
+    fn spades_finished_test() {
+        let log = Path::new("test_files/spades_test/spades.log");
+        let unfinished = Path::new("test_files/spade_runner.ini");
+        let missing = Path::new("test_files/spades.log");
+
+        assert!(is_spades_finished(log));
+        assert!(!is_spades_finished(unfinished));
+        assert!(!is_spades_finished(missing));
     }
 }
\ No newline at end of file
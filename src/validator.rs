@@ -0,0 +1,162 @@
+// Checks a config file before running the batch.
+// Reports every problem at once with the line number of the sample.
+use std::collections::HashMap;
+use std::io::{self, Result, Write};
+use std::path::Path;
+use std::slice;
+
+use crate::error::Error;
+use crate::finder::{self, ReadPatterns};
+use crate::parser::{self, SeqDirs};
+
+#[derive(Debug)]
+struct Problem {
+    line: usize,
+    id: String,
+    msg: String,
+}
+
+impl Problem {
+    fn new(sample: &SeqDirs, msg: String) -> Self {
+        Self {
+            line: sample.line,
+            id: sample.id.clone(),
+            msg,
+        }
+    }
+
+    fn from_error(err: &Error) -> Self {
+        match err {
+            Error::Config { line, msg } => Self { line: *line, id: String::new(), msg: msg.clone() },
+            _ => Self { line: 0, id: String::new(), msg: err.to_string() },
+        }
+    }
+}
+
+pub fn validate_config(input: &str, patterns: &ReadPatterns) -> std::result::Result<(), Error> {
+    let (samples, errors) = parser::parse_seqdir_lines(input)?;
+    let mut problems: Vec<Problem> = errors.iter()
+        .map(Problem::from_error)
+        .collect();
+    problems.extend(find_duplicate_ids(&samples));
+    samples.iter()
+        .for_each(|s| problems.extend(check_sample(s, patterns)));
+    problems.sort_by_key(|p| p.line);
+
+    print_problems(input, samples.len(), &problems)?;
+
+    if !problems.is_empty() {
+        return Err(Error::InvalidInput(format!("FOUND {} PROBLEMS IN {}",
+            problems.len(), input)));
+    }
+
+    Ok(())
+}
+
+// The ID is used as the output directory name.
+pub fn is_safe_id(id: &str) -> bool {
+    !id.is_empty()
+        && id != "."
+        && id != ".."
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn find_duplicate_ids(samples: &[SeqDirs]) -> Vec<Problem> {
+    let mut lines: HashMap<&str, usize> = HashMap::new();
+    samples.iter()
+        .filter_map(|s| match lines.get(s.id.as_str()) {
+            Some(first) => Some(Problem::new(s,
+                format!("DUPLICATE ID. THE ID IS FIRST USED AT LINE {}", first))),
+            None => {
+                lines.insert(&s.id, s.line);
+                None
+            }
+        })
+        .collect()
+}
+
+fn check_sample(sample: &SeqDirs, patterns: &ReadPatterns) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if !is_safe_id(&sample.id) {
+        problems.push(Problem::new(sample, String::from("UNSAFE CHARACTERS IN THE ID. \
+            PLEASE USE LETTERS, NUMBERS, '.', '_', OR '-' ONLY")));
+    }
+
+    if !sample.dir.is_empty() && !Path::new(&sample.dir).is_dir() {
+        problems.push(Problem::new(sample, format!("DIRECTORY NOT FOUND: {}", sample.dir)));
+        // Without listed reads, there is nothing else to check.
+        if sample.read_1.is_none() {
+            return problems;
+        }
+    }
+
+    let res = finder::find_cleaned_fastq(slice::from_ref(sample), patterns);
+    res.failed.iter()
+        .for_each(|(_, e)| problems.push(Problem::new(sample, e.to_string())));
+    res.reads.iter()
+        .flat_map(|r| r.get_warnings())
+        .for_each(|w| problems.push(Problem::new(sample, w)));
+
+    problems
+}
+
+fn print_problems(input: &str, samples: usize, problems: &[Problem]) -> Result<()> {
+    let out = io::stdout();
+    let mut handle = io::BufWriter::new(out);
+
+    writeln!(handle, "\x1b[0;33mConfig Validation\x1b[0m")?;
+    writeln!(handle, "Config\t\t: {}", input)?;
+    writeln!(handle, "Samples\t\t: {}", samples)?;
+    writeln!(handle, "Problems\t: {}\n", problems.len())?;
+
+    problems.iter()
+        .try_for_each(|p| {
+            let line = match p.line {
+                0 => String::from("-"),
+                line => line.to_string(),
+            };
+            if p.id.is_empty() {
+                writeln!(handle, "\x1b[0;31mLine {}\x1b[0m\t\t: {}", line, p.msg)
+            } else {
+                writeln!(handle, "\x1b[0;31mLine {}\x1b[0m\t\t: {}. {}", line, p.id, p.msg)
+            }
+        })?;
+
+    if problems.is_empty() {
+        writeln!(handle, "\x1b[0;32mNo problems found.\x1b[0m")?;
+    }
+    writeln!(handle)?;
+
+    handle.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn safe_id_test() {
+        assert!(is_safe_id("Bunomys_chrysocomus-LSUMZ.1"));
+        assert!(!is_safe_id("some animals"));
+        assert!(!is_safe_id("some/animals"));
+        assert!(!is_safe_id(".."));
+        assert!(!is_safe_id(""));
+    }
+
+    #[test]
+    fn validate_config_test() {
+        let dirs = parser::parse_seqdir("test_files/validate_test.csv").unwrap();
+        let mut problems = find_duplicate_ids(&dirs);
+        dirs.iter()
+            .for_each(|s| problems.extend(check_sample(s, &ReadPatterns::default())));
+        let msgs: Vec<(usize, &str)> = problems.iter()
+            .map(|p| (p.line, p.msg.as_str()))
+            .collect();
+
+        assert!(msgs.contains(&(5, "DUPLICATE ID. THE ID IS FIRST USED AT LINE 2")));
+        assert!(msgs.contains(&(3, "DIRECTORY NOT FOUND: test_files/missing")));
+        assert!(msgs.iter().any(|(line, msg)| *line == 4 && msg.starts_with("NO READS FOUND")));
+        assert!(msgs.iter().any(|(line, msg)| *line == 2 && msg.starts_with("EMPTY FILE")));
+        assert!(msgs.iter().any(|(line, msg)| *line == 6 && msg.starts_with("UNSAFE CHARACTERS")));
+    }
+}